@@ -53,6 +53,18 @@ pub fn approx_size_mean_after(mins: &[u64]) -> usize {
     mean(&trans) as usize
 }
 
+/// Maximum likelihood estimation:
+/// The minimum of n uniform values in [0,1] has density n(1-u)^(n-1).
+/// Maximizing the likelihood of all the minimums gives k / sum(-ln(1 - u_i)).
+pub fn approx_size_mle(mins: &[u64]) -> usize {
+    let fsum: f64 = mins.iter()
+        .map(|&m| (m as f64) / (u64::max_value() as f64))
+        .map(|u| -(-u).ln_1p())
+        .sum();
+
+    ((mins.len() as f64) / fsum) as usize
+}
+
 pub type ApproxFunc = (Fn(&[u64]) -> usize +  std::marker::Sync);
 
 pub static APPROX_FUNCS_NAMED: &[(&ApproxFunc, &'static str)] = 
@@ -61,6 +73,7 @@ pub static APPROX_FUNCS_NAMED: &[(&ApproxFunc, &'static str)] =
         (&approx_size_harmonic_after, "approx_size_harmonic_after"),
         (&approx_size_mean_before, "approx_size_mean_before"),
         (&approx_size_mean_after, "approx_size_mean_after"),
+        (&approx_size_mle, "approx_size_mle"),
     ];
 
 
@@ -74,4 +87,14 @@ mod tests {
         approx_size_harmonic_before(mins);
     }
 
+    #[test]
+    fn test_approx_size_mle() {
+        // Minimums of a set of size about 100:
+        let mins = (1 .. 41)
+            .map(|i| (u64::max_value() / 2050) * i)
+            .collect::<Vec<u64>>();
+        let approx_size = approx_size_mle(&mins);
+        assert!(approx_size > 80 && approx_size < 120);
+    }
+
 }
@@ -0,0 +1,47 @@
+/*
+ * Estimate the size of networks using gossip of minimum hashes.
+ * Every node starts with the minimum hashes of its own id, and repeatedly merges the
+ * minimum hashes of its neighbors. After convergence every node applies all the
+ * approximation functions.
+ */
+
+extern crate rand;
+extern crate approximate_net;
+extern crate net_coords;
+
+use self::rand::{StdRng};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use approximate_net::gossip::{eval_gossip_size};
+use approximate_net::approx_funcs::{APPROX_FUNCS_NAMED, ApproxFunc};
+
+fn main() {
+    let num_mins = 40;
+    let experiment_seed = 0x1337;
+
+    println!("Estimating network size using gossip of minimum hashes");
+    println!("num_mins  = {}", num_mins);
+    println!();
+
+    let approx_funcs = APPROX_FUNCS_NAMED.iter()
+        .map(|&(approx_func, _)| approx_func)
+        .collect::<Vec<&ApproxFunc>>();
+
+    for g in 6 .. 14 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all() { // Iterate over type of network
+            let seed: &[_] = &[experiment_seed, g, kind.net_type()];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+
+            let eval = eval_gossip_size(&net, num_mins, approx_funcs.as_slice());
+            println!("g={:2}; {:8}; num_nodes={:6}; rounds={:4}", 
+                     g, kind.name(), net.igraph.node_count(), eval.rounds);
+            for (i,&(_, func_name)) in APPROX_FUNCS_NAMED.iter().enumerate() {
+                println!("    {:30} : mean_err_ratio={:10.4} max_err_ratio={:10.4}", 
+                         func_name, eval.mean_err_ratio(i), eval.max_err_ratio(i));
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,46 @@
+/*
+ * Benchmark the hash families: Amount of elements hashed per second,
+ * when calculating the minimum of every hash function over a set of elements.
+ */
+
+extern crate rand;
+extern crate approximate_net;
+
+use std::time::{Instant};
+
+use self::rand::{Rng, StdRng};
+
+use approximate_net::hash_family::{hash_families};
+
+fn main() {
+    let num_hashes = 40;
+    let num_elems = 100000;
+    let experiment_seed = 0x1337;
+
+    println!("Benchmarking hash families");
+    println!("num_hashes = {}", num_hashes);
+    println!("num_elems  = {}", num_elems);
+    println!();
+
+    let seed: &[_] = &[experiment_seed];
+    let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+    let elems = (0 .. num_elems)
+        .map(|_| rng.gen::<u64>())
+        .collect::<Vec<u64>>();
+
+    for hash_family in hash_families(num_hashes) {
+        let start = Instant::now();
+        let mins = (0 .. num_hashes)
+            .map(|hash_index| elems.iter()
+                 .map(|&x| hash_family.hash(hash_index, x))
+                 .min().unwrap())
+            .collect::<Vec<u64>>();
+        let elapsed = start.elapsed();
+        let secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+
+        let num_hashed = (num_hashes * num_elems) as f64;
+        // Print one of the minimums, so that the calculation is not optimized away:
+        println!("{:16} : {:12.0} elems/sec ({:8.3} secs) min[0] = {:016x}",
+                 hash_family.name(), num_hashed / secs, secs, mins[0]);
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * Show how the error of the size estimators scales with the amount of registers
+ * (num_mins) and the size of the estimated set (num_elems).
+ * Hash family may be given as a command line argument (Default: sha256).
+ */
+
+extern crate rand;
+extern crate approximate_net;
+
+use std::env;
+
+use self::rand::{StdRng};
+
+use approximate_net::{eval_size_estimators};
+use approximate_net::estimators::{size_estimators, SizeEstimator};
+use approximate_net::hash_family::{hash_family_by_name};
+
+fn main() {
+    let num_iters = 20;
+    let experiment_seed = 0x1337;
+    let max_num_mins_log = 8;
+
+    let hash_family_name = env::args().nth(1).unwrap_or_else(|| "sha256".to_string());
+    let hash_family = hash_family_by_name(&hash_family_name, 1 << max_num_mins_log)
+        .unwrap_or_else(|| panic!("Unknown hash family: {}", hash_family_name));
+
+    println!("Error of size estimators for various num_mins and num_elems");
+    println!("num_iters = {}", num_iters);
+    println!("hash_family = {}", hash_family.name());
+    println!();
+
+    let estimators = size_estimators();
+    let estimators_refs = estimators.iter()
+        .map(|estimator| estimator.as_ref())
+        .collect::<Vec<&dyn SizeEstimator>>();
+
+    print!("{:30} | {:>8} | {:>9} |", "estimator", "num_mins", "num_elems");
+    println!(" {:>6} | {:>9} | {:>19} | {:>8}", "bytes", "err_ratio", "ci", "bias");
+
+    for num_mins_log in 3 .. max_num_mins_log { // Iterate over amount of registers
+        let num_mins = 1 << num_mins_log;
+        for num_elems_log in 2 .. 6 { // Iterate over size of set
+            let num_elems = 10_usize.pow(num_elems_log);
+            let seed: &[_] = &[experiment_seed, num_mins, num_elems];
+            let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let evals = eval_size_estimators(num_iters, num_mins, num_elems,
+                                             estimators_refs.as_slice(), hash_family.as_ref(),
+                                             &mut rng);
+
+            for (estimator, eval) in estimators.iter().zip(evals.iter()) {
+                let report = &eval.report;
+                print!("{:30} | {:8} | {:9} |", estimator.name(), num_mins, num_elems);
+                println!(" {:6} | {:9.4} | [{:8.4}, {:8.4}] | {:+8.4}",
+                         eval.memory_bytes, report.err_ratio,
+                         report.err_ratio_ci.0, report.err_ratio_ci.1, report.bias);
+            }
+            println!();
+        }
+    }
+}
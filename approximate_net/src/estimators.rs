@@ -0,0 +1,291 @@
+use std::collections::BTreeSet;
+
+use approx_funcs::{ApproxFunc, APPROX_FUNCS_NAMED};
+use hash_family::{HashFamily};
+use calc_mins;
+
+/// A method for estimating the size of a set.
+/// A sketch of num_regs registers is calculated from the set, and the size is estimated
+/// from the sketch alone.
+pub trait SizeEstimator: Sync {
+    /// Short name of the estimator, used for printing experiments results.
+    fn name(&self) -> &str;
+
+    /// Name of the kind of sketch used. Estimators with the same kind of sketch
+    /// can share the same sketch.
+    fn sketch_name(&self) -> &str;
+
+    /// Calculate a sketch of num_regs registers from a set of elements, using functions
+    /// of hash_family. hash_family must contain at least max(num_regs, 2) functions.
+    fn sketch(&self, elems: &[u64], num_regs: usize, hash_family: &dyn HashFamily) -> Vec<u64>;
+
+    /// Estimate the size of a set from its sketch.
+    fn estimate(&self, sketch: &[u64]) -> usize;
+
+    /// Amount of bytes required to keep one register of the sketch.
+    fn register_bytes(&self) -> usize;
+}
+
+/// Estimate using the minimum of every hash function (See approx_funcs).
+pub struct MinsEstimator<'a> {
+    pub name: &'a str,
+    pub approx_func: &'a ApproxFunc,
+}
+
+/// HyperLogLog: Every element is assigned to a register by one hash function.
+/// A register keeps the maximum position of the first 1 bit of another hash function.
+pub struct HyperLogLogEstimator;
+
+/// Bottom-k (K Minimum Values): The k smallest distinct values of a single hash function.
+/// k is the amount of registers. Registers that are not filled by a value are KMV_EMPTY.
+pub struct KmvEstimator;
+
+/// Marks an unused register of a KMV sketch. A set with less than k distinct hashes
+/// leaves some registers unused, and its size is known exactly.
+pub const KMV_EMPTY: u64 = u64::max_value();
+
+/// Flajolet-Martin with stochastic averaging (PCSA): Every element is assigned to a
+/// bitmap by one hash function. The bitmap keeps the positions of the lowest 1 bit of
+/// another hash function.
+pub struct FmEstimator;
+
+
+impl<'a> SizeEstimator for MinsEstimator<'a> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn sketch_name(&self) -> &str {
+        "mins"
+    }
+
+    fn sketch(&self, elems: &[u64], num_regs: usize, hash_family: &dyn HashFamily) -> Vec<u64> {
+        calc_mins(elems, num_regs, hash_family)
+    }
+
+    fn estimate(&self, sketch: &[u64]) -> usize {
+        (self.approx_func)(sketch)
+    }
+
+    fn register_bytes(&self) -> usize {
+        8
+    }
+}
+
+/// Register number of an element, for estimators that use stochastic averaging.
+fn register_index(x: u64, num_regs: usize, hash_family: &dyn HashFamily) -> usize {
+    (hash_family.hash(0, x) % (num_regs as u64)) as usize
+}
+
+impl SizeEstimator for HyperLogLogEstimator {
+    fn name(&self) -> &str {
+        "hyperloglog"
+    }
+
+    fn sketch_name(&self) -> &str {
+        "hyperloglog"
+    }
+
+    fn sketch(&self, elems: &[u64], num_regs: usize, hash_family: &dyn HashFamily) -> Vec<u64> {
+        let mut regs = vec![0; num_regs];
+        for &x in elems {
+            let reg_index = register_index(x, num_regs, hash_family);
+            let rank = (hash_family.hash(1, x).leading_zeros() + 1) as u64;
+            if rank > regs[reg_index] {
+                regs[reg_index] = rank;
+            }
+        }
+        regs
+    }
+
+    fn estimate(&self, sketch: &[u64]) -> usize {
+        let m = sketch.len() as f64;
+        let alpha = match sketch.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let fsum: f64 = sketch.iter()
+            .map(|&rank| 2.0_f64.powi(-(rank as i32)))
+            .sum();
+        let raw_estimate = alpha * m * m / fsum;
+
+        // Small range correction (Linear counting):
+        let num_zeros = sketch.iter().filter(|&&rank| rank == 0).count();
+        if raw_estimate <= 2.5 * m && num_zeros > 0 {
+            (m * (m / (num_zeros as f64)).ln()) as usize
+        } else {
+            raw_estimate as usize
+        }
+    }
+
+    fn register_bytes(&self) -> usize {
+        // A rank is at most 65, which fits in one byte:
+        1
+    }
+}
+
+impl SizeEstimator for KmvEstimator {
+    fn name(&self) -> &str {
+        "kmv"
+    }
+
+    fn sketch_name(&self) -> &str {
+        "kmv"
+    }
+
+    fn sketch(&self, elems: &[u64], num_regs: usize, hash_family: &dyn HashFamily) -> Vec<u64> {
+        let mut smallest: BTreeSet<u64> = BTreeSet::new();
+        for &x in elems {
+            smallest.insert(hash_family.hash(0, x));
+            if smallest.len() > num_regs {
+                let largest = *smallest.iter().next_back().unwrap();
+                smallest.remove(&largest);
+            }
+        }
+        let mut sketch = smallest.into_iter().collect::<Vec<u64>>();
+        sketch.resize(num_regs, KMV_EMPTY);
+        sketch
+    }
+
+    fn estimate(&self, sketch: &[u64]) -> usize {
+        let k = sketch.len();
+        let num_values = sketch.iter().filter(|&&value| value != KMV_EMPTY).count();
+        // A sketch that is not full contains all the hashed elements.
+        // At least two values are required for an estimation:
+        if num_values < k || k <= 1 {
+            return num_values;
+        }
+        let kth_min = (sketch[k - 1] as f64) / (u64::max_value() as f64);
+        (((k - 1) as f64) / kth_min) as usize
+    }
+
+    fn register_bytes(&self) -> usize {
+        8
+    }
+}
+
+/// The magic constant of Flajolet-Martin.
+const FM_PHI: f64 = 0.77351;
+
+impl SizeEstimator for FmEstimator {
+    fn name(&self) -> &str {
+        "fm_pcsa"
+    }
+
+    fn sketch_name(&self) -> &str {
+        "fm_pcsa"
+    }
+
+    fn sketch(&self, elems: &[u64], num_regs: usize, hash_family: &dyn HashFamily) -> Vec<u64> {
+        let mut bitmaps = vec![0; num_regs];
+        for &x in elems {
+            let reg_index = register_index(x, num_regs, hash_family);
+            let rho = hash_family.hash(1, x).trailing_zeros().min(63);
+            bitmaps[reg_index] |= 1 << rho;
+        }
+        bitmaps
+    }
+
+    fn estimate(&self, sketch: &[u64]) -> usize {
+        let m = sketch.len() as f64;
+        // Position of the lowest 0 bit of every bitmap:
+        let sum_r: u32 = sketch.iter()
+            .map(|&bitmap| (!bitmap).trailing_zeros())
+            .sum();
+        let mean_r = (sum_r as f64) / m;
+        ((m / FM_PHI) * 2.0_f64.powf(mean_r)) as usize
+    }
+
+    fn register_bytes(&self) -> usize {
+        8
+    }
+}
+
+/// All size estimators: The estimators of APPROX_FUNCS_NAMED, followed by the estimators
+/// that use other kinds of sketches.
+pub fn size_estimators() -> Vec<Box<dyn SizeEstimator>> {
+    let mut estimators: Vec<Box<dyn SizeEstimator>> = APPROX_FUNCS_NAMED.iter()
+        .map(|&(approx_func, name)| -> Box<dyn SizeEstimator> {
+            Box::new(MinsEstimator {name, approx_func})
+        })
+        .collect();
+    estimators.push(Box::new(HyperLogLogEstimator));
+    estimators.push(Box::new(KmvEstimator));
+    estimators.push(Box::new(FmEstimator));
+    estimators
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use gen_elems;
+    use hash_family::{Sha256Family, hash_families};
+
+    #[test]
+    fn test_size_estimators_accuracy() {
+        let seed: &[_] = &[1,2,3,4];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let num_elems = 5000;
+        let num_regs = 64;
+        let elems = gen_elems(num_elems, &mut rng);
+
+        // approx_size_harmonic_before and approx_size_mean_after are not checked: A single
+        // sketch of these estimators can be off by an order of magnitude.
+        for (estimator, hash_family) in size_estimators().iter()
+                .filter(|estimator| estimator.name() != "approx_size_harmonic_before" &&
+                        estimator.name() != "approx_size_mean_after")
+                .flat_map(|estimator| hash_families(num_regs).into_iter()
+                          .map(move |hash_family| (estimator, hash_family))) {
+            let sketch = estimator.sketch(&elems, num_regs, hash_family.as_ref());
+            assert!(sketch.len() == num_regs, "{}", estimator.name());
+            let approx_size = estimator.estimate(&sketch) as f64;
+            let err_ratio = (approx_size - (num_elems as f64)).abs() / (num_elems as f64);
+            assert!(err_ratio < 0.5, "{} {}: {}", estimator.name(), hash_family.name(),
+                    err_ratio);
+        }
+    }
+
+    #[test]
+    fn test_kmv_small_set() {
+        let elems = vec![1,2,3,3,2];
+        let sketch = KmvEstimator.sketch(&elems, 10, &Sha256Family);
+        // All distinct hashes are kept, and the rest of the registers are unused:
+        assert!(sketch.len() == 10);
+        assert!(sketch[.. 3].windows(2).all(|w| w[0] < w[1]));
+        assert!(sketch[3 ..].iter().all(|&value| value == KMV_EMPTY));
+        assert!(KmvEstimator.estimate(&sketch) == 3);
+
+        let empty_sketch = KmvEstimator.sketch(&[], 10, &Sha256Family);
+        assert!(empty_sketch == vec![KMV_EMPTY; 10]);
+        assert!(KmvEstimator.estimate(&empty_sketch) == 0);
+        assert!(KmvEstimator.estimate(&[]) == 0);
+    }
+
+    #[test]
+    fn test_kmv_exact_below_k() {
+        // Less than k distinct elements are counted exactly:
+        let elems = (0 .. 5).collect::<Vec<u64>>();
+        for hash_family in hash_families(10) {
+            let sketch = KmvEstimator.sketch(&elems, 10, hash_family.as_ref());
+            assert!(KmvEstimator.estimate(&sketch) == 5, "{}", hash_family.name());
+        }
+    }
+
+    #[test]
+    fn test_hyperloglog_small_range() {
+        // Linear counting is exact enough for tiny sets:
+        let elems = (0 .. 10).collect::<Vec<u64>>();
+        let sketch = HyperLogLogEstimator.sketch(&elems, 256, &Sha256Family);
+        let approx_size = HyperLogLogEstimator.estimate(&sketch);
+        assert!((9 ..= 11).contains(&approx_size));
+        // Duplicates do not change the sketch:
+        let mut dup_elems = elems.clone();
+        dup_elems.extend(elems.iter().cloned());
+        assert!(HyperLogLogEstimator.sketch(&dup_elems, 256, &Sha256Family) == sketch);
+    }
+}
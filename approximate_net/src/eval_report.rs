@@ -0,0 +1,122 @@
+extern crate rand;
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+
+/// Quantiles of the relative error that are reported.
+pub const ERR_QUANTILES: &[f64] = &[0.05, 0.25, 0.5, 0.75, 0.95];
+
+/// Amount of resamples used for the bootstrap confidence interval.
+const NUM_RESAMPLES: usize = 200;
+
+/// Confidence level of the bootstrap confidence interval.
+const CONFIDENCE: f64 = 0.95;
+
+/// Summary of the errors of a size estimator over many experiments.
+/// The relative error of one experiment is (approx_size - size) / size.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    /// Mean relative error.
+    pub bias: f64,
+    /// Root mean square of the relative error.
+    pub err_ratio: f64,
+    /// Relative error at every quantile of ERR_QUANTILES.
+    pub err_quantiles: Vec<f64>,
+    /// Bootstrap confidence interval for err_ratio.
+    pub err_ratio_ci: (f64, f64),
+}
+
+/// Value at quantile q of sorted values (Nearest rank).
+fn quantile(sorted_vals: &[f64], q: f64) -> f64 {
+    let index = ((q * (sorted_vals.len() as f64)).ceil() as usize).max(1) - 1;
+    sorted_vals[index.min(sorted_vals.len() - 1)]
+}
+
+/// Root mean square of values.
+fn root_mean_square(vals: &[f64]) -> f64 {
+    let sum_squares: f64 = vals.iter()
+        .map(|&x| x * x)
+        .sum();
+    (sum_squares / (vals.len() as f64)).sqrt()
+}
+
+/// Calculate a bootstrap confidence interval for the root mean square of values:
+/// Values are resampled with replacement, and the root mean square of every resample
+/// is calculated.
+fn bootstrap_rms_ci(vals: &[f64], mut rng: &mut dyn Rng) -> (f64, f64) {
+    let index_range: Range<usize> = Range::new(0, vals.len());
+    let mut resampled_rms = (0 .. NUM_RESAMPLES)
+        .map(|_| {
+            let resample = (0 .. vals.len())
+                .map(|_| vals[index_range.ind_sample(&mut rng)])
+                .collect::<Vec<f64>>();
+            root_mean_square(&resample)
+        })
+        .collect::<Vec<f64>>();
+    resampled_rms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let alpha = (1.0 - CONFIDENCE) / 2.0;
+    (quantile(&resampled_rms, alpha), quantile(&resampled_rms, 1.0 - alpha))
+}
+
+/// Create an error report from relative errors of experiments.
+pub fn error_report(rel_errs: &[f64], rng: &mut dyn Rng) -> ErrorReport {
+    assert!(!rel_errs.is_empty());
+
+    let bias = rel_errs.iter().sum::<f64>() / (rel_errs.len() as f64);
+    let mut sorted_errs = rel_errs.to_vec();
+    sorted_errs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    ErrorReport {
+        bias,
+        err_ratio: root_mean_square(rel_errs),
+        err_quantiles: ERR_QUANTILES.iter()
+            .map(|&q| quantile(&sorted_errs, q))
+            .collect::<Vec<f64>>(),
+        err_ratio_ci: bootstrap_rms_ci(rel_errs, rng),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+
+    #[test]
+    fn test_quantile() {
+        let vals = &[1.0, 2.0, 3.0, 4.0];
+        assert_eq!(quantile(vals, 0.0), 1.0);
+        assert_eq!(quantile(vals, 0.25), 1.0);
+        assert_eq!(quantile(vals, 0.5), 2.0);
+        assert_eq!(quantile(vals, 0.51), 3.0);
+        assert_eq!(quantile(vals, 1.0), 4.0);
+    }
+
+    #[test]
+    fn test_error_report() {
+        let seed: &[_] = &[1,2,3,4];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+
+        let rel_errs = &[0.1, -0.1, 0.3, -0.1];
+        let report = error_report(rel_errs, &mut rng);
+        assert!((report.bias - 0.05).abs() < 1e-9);
+        assert!((report.err_ratio - 0.03_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(report.err_quantiles.len(), ERR_QUANTILES.len());
+        assert!(report.err_quantiles.windows(2).all(|w| w[0] <= w[1]));
+        let (low, high) = report.err_ratio_ci;
+        assert!(0.1 - 1e-9 <= low && low <= report.err_ratio);
+        assert!(report.err_ratio <= high && high <= 0.3 + 1e-9);
+    }
+
+    #[test]
+    fn test_error_report_no_errors() {
+        let seed: &[_] = &[1,2,3,4];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+
+        let report = error_report(&[0.0; 10], &mut rng);
+        assert_eq!(report.bias, 0.0);
+        assert_eq!(report.err_ratio, 0.0);
+        assert_eq!(report.err_ratio_ci, (0.0, 0.0));
+    }
+}
@@ -0,0 +1,154 @@
+extern crate net_coords;
+
+use self::net_coords::network::{Network};
+use self::net_coords::chord::{RingKey};
+
+use approx_funcs::{ApproxFunc};
+use hash_elem;
+
+/// Initial minimum hashes of every node: The hashes of its own id.
+pub fn init_gossip_mins(net: &Network<RingKey>, num_mins: usize) -> Vec<Vec<u64>> {
+    (0 .. net.igraph.node_count())
+        .map(|x_i| {
+            let node_id = *net.index_to_node(x_i).unwrap();
+            (0 .. num_mins)
+                .map(|hash_index| hash_elem(hash_index, node_id))
+                .collect::<Vec<u64>>()
+        })
+        .collect::<Vec<Vec<u64>>>()
+}
+
+/// Perform one round of gossip: Every node merges the minimum hashes of all its neighbors
+/// (As they were at the beginning of the round) into its own.
+/// Returns the amount of nodes that have changed.
+fn gossip_round(net: &Network<RingKey>, mins: &mut Vec<Vec<u64>>) -> usize {
+    let mut new_mins = mins.clone();
+    let mut nodes_changed = 0;
+
+    for x_i in 0 .. net.igraph.node_count() {
+        for nei_i in net.igraph.neighbors(x_i) {
+            for hash_index in 0 .. mins[x_i].len() {
+                if mins[nei_i][hash_index] < new_mins[x_i][hash_index] {
+                    new_mins[x_i][hash_index] = mins[nei_i][hash_index];
+                }
+            }
+        }
+        if new_mins[x_i] != mins[x_i] {
+            nodes_changed += 1;
+        }
+    }
+
+    *mins = new_mins;
+    nodes_changed
+}
+
+/// Run gossip rounds until no node changes.
+/// Returns the minimum hashes of every node and the amount of rounds in which
+/// some node has changed.
+pub fn converge_gossip_mins(net: &Network<RingKey>, num_mins: usize) -> (Vec<Vec<u64>>, usize) {
+    let mut mins = init_gossip_mins(net, num_mins);
+    let mut rounds = 0;
+    while gossip_round(net, &mut mins) > 0 {
+        rounds += 1;
+    }
+    (mins, rounds)
+}
+
+/// Results of estimating the size of a network using gossip.
+#[derive(Debug)]
+pub struct GossipSizeEval {
+    /// Amount of gossip rounds until convergence.
+    pub rounds: usize,
+    /// For every approximation function: Relative estimation error of every node.
+    pub node_err_ratios: Vec<Vec<f64>>,
+}
+
+impl GossipSizeEval {
+    /// Mean relative error over all nodes, for approximation function number func_index.
+    pub fn mean_err_ratio(&self, func_index: usize) -> f64 {
+        let err_ratios = &self.node_err_ratios[func_index];
+        err_ratios.iter().sum::<f64>() / (err_ratios.len() as f64)
+    }
+
+    /// Maximum relative error over all nodes, for approximation function number func_index.
+    pub fn max_err_ratio(&self, func_index: usize) -> f64 {
+        self.node_err_ratios[func_index].iter()
+            .cloned()
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Estimate the size of the network: Every node gossips minimum hashes until convergence,
+/// and then applies every approximation function to its minimum hashes.
+pub fn eval_gossip_size(net: &Network<RingKey>, num_mins: usize,
+                        approx_size_funcs: &[&ApproxFunc]) -> GossipSizeEval {
+
+    let (mins, rounds) = converge_gossip_mins(net, num_mins);
+    let num_nodes = net.igraph.node_count() as f64;
+
+    let node_err_ratios = approx_size_funcs.iter()
+        .map(|&approx_size_func| mins.iter()
+             .map(|node_mins| {
+                 let approx_size = approx_size_func(node_mins) as f64;
+                 (approx_size - num_nodes).abs() / num_nodes
+             })
+             .collect::<Vec<f64>>())
+        .collect::<Vec<Vec<f64>>>();
+
+    GossipSizeEval {
+        rounds,
+        node_err_ratios,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use self::net_coords::network_gen::{gen_network_kind, NetworkKind};
+    use approx_funcs::{approx_size_harmonic_after};
+
+    /// A path network of num_nodes nodes.
+    fn path_net(num_nodes: usize) -> Network<RingKey> {
+        let mut net = Network::<RingKey>::new();
+        for x in 0 .. num_nodes {
+            net.add_node(x as RingKey);
+        }
+        for x in 0 .. num_nodes - 1 {
+            net.igraph.add_edge(x, x + 1, 1);
+        }
+        net
+    }
+
+    #[test]
+    fn test_converge_gossip_mins_path() {
+        let net = path_net(10);
+        let init_mins = init_gossip_mins(&net, 8);
+        let (mins, rounds) = converge_gossip_mins(&net, 8);
+        // Information has to travel along the whole path at most:
+        assert!(rounds <= 9);
+        for hash_index in 0 .. 8 {
+            let global_min = init_mins.iter()
+                .map(|node_mins| node_mins[hash_index])
+                .min().unwrap();
+            assert!(mins.iter().all(|node_mins| node_mins[hash_index] == global_min));
+        }
+    }
+
+    #[test]
+    fn test_eval_gossip_size() {
+        let seed: &[_] = &[1,2,3,4];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        for kind in NetworkKind::all() {
+            let net = gen_network_kind(kind, 8, 17, 1, 2, &mut rng);
+            let eval = eval_gossip_size(&net, 40, &[&approx_size_harmonic_after]);
+            assert!(eval.rounds > 0);
+            assert!(eval.node_err_ratios[0].len() == net.igraph.node_count());
+            // All nodes have the same minimum hashes, hence the same estimation:
+            assert!(eval.max_err_ratio(0) - eval.mean_err_ratio(0) < 1e-9);
+            assert!(eval.max_err_ratio(0) < 0.6);
+        }
+    }
+}
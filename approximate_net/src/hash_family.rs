@@ -0,0 +1,280 @@
+use hash_elem;
+
+/// Seed used to generate the keys of the keyed hash families.
+pub const HASH_FAMILY_SEED: u64 = 0x1337;
+
+/// A family of hash functions from u64 to u64.
+/// Function number hash_index of the family is applied by hash(hash_index, x).
+pub trait HashFamily: Sync {
+    /// Short name of the family, used for printing experiments results.
+    fn name(&self) -> &str;
+
+    /// Hash a given u64 value using function number hash_index.
+    fn hash(&self, hash_index: usize, x: u64) -> u64;
+}
+
+/// The reference family: sha256 over the serialized hash_index and x. (See hash_elem)
+pub struct Sha256Family;
+
+/// SipHash-2-4, keyed by the hash_index.
+pub struct SipHashFamily;
+
+/// Multiply-shift: h(x) = ((a*mix(x) + b) mod 2^128) >> 64, for random 128 bit a, b.
+///
+/// mix is the (bijective) finalizer of splitmix64. Without it, the values over an arithmetic
+/// progression (For example consecutive elements) form a lattice, and the minimums are much
+/// larger than those of a random function.
+pub struct MultiplyShiftFamily {
+    keys: Vec<(u128, u128)>,
+}
+
+/// Simple tabulation: Every byte of x selects a random value from a table,
+/// and all the selected values are xored.
+pub struct TabulationFamily {
+    tables: Vec<[[u64; 256]; 8]>,
+}
+
+/// Finalizer of splitmix64. A bijection of u64 that mixes all the bits of z.
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Generate a pseudo random number from a state (splitmix64).
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    mix64(*state)
+}
+
+impl HashFamily for Sha256Family {
+    fn name(&self) -> &str {
+        "sha256"
+    }
+
+    fn hash(&self, hash_index: usize, x: u64) -> u64 {
+        hash_elem(hash_index, x)
+    }
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// SipHash-2-4 of the 8 little endian bytes of x.
+fn siphash24(k0: u64, k1: u64, x: u64) -> u64 {
+    let mut v = [k0 ^ 0x736f_6d65_7073_6575,
+                 k1 ^ 0x646f_7261_6e64_6f6d,
+                 k0 ^ 0x6c79_6765_6e65_7261,
+                 k1 ^ 0x7465_6462_7974_6573];
+
+    // The only message block:
+    v[3] ^= x;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= x;
+
+    // Last block, containing only the message length:
+    let last_block = 8_u64 << 56;
+    v[3] ^= last_block;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= last_block;
+
+    // Finalization:
+    v[2] ^= 0xff;
+    for _ in 0 .. 4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+impl HashFamily for SipHashFamily {
+    fn name(&self) -> &str {
+        "siphash"
+    }
+
+    fn hash(&self, hash_index: usize, x: u64) -> u64 {
+        siphash24(hash_index as u64, HASH_FAMILY_SEED, x)
+    }
+}
+
+impl MultiplyShiftFamily {
+    /// Create a family of num_hashes functions, with keys generated from seed.
+    pub fn new(num_hashes: usize, seed: u64) -> Self {
+        let mut state = seed;
+        let mut gen_u128 = || {
+            let high = splitmix64(&mut state) as u128;
+            let low = splitmix64(&mut state) as u128;
+            (high << 64) | low
+        };
+        let keys = (0 .. num_hashes)
+            .map(|_| (gen_u128(), gen_u128()))
+            .collect::<Vec<(u128, u128)>>();
+
+        MultiplyShiftFamily {keys}
+    }
+}
+
+impl HashFamily for MultiplyShiftFamily {
+    fn name(&self) -> &str {
+        "multiply_shift"
+    }
+
+    fn hash(&self, hash_index: usize, x: u64) -> u64 {
+        let (a, b) = self.keys[hash_index];
+        (a.wrapping_mul(mix64(x) as u128).wrapping_add(b) >> 64) as u64
+    }
+}
+
+impl TabulationFamily {
+    /// Create a family of num_hashes functions, with tables generated from seed.
+    pub fn new(num_hashes: usize, seed: u64) -> Self {
+        let mut state = seed;
+        let tables = (0 .. num_hashes)
+            .map(|_| {
+                let mut table = [[0_u64; 256]; 8];
+                for byte_table in table.iter_mut() {
+                    for entry in byte_table.iter_mut() {
+                        *entry = splitmix64(&mut state);
+                    }
+                }
+                table
+            })
+            .collect::<Vec<[[u64; 256]; 8]>>();
+
+        TabulationFamily {tables}
+    }
+}
+
+impl HashFamily for TabulationFamily {
+    fn name(&self) -> &str {
+        "tabulation"
+    }
+
+    fn hash(&self, hash_index: usize, x: u64) -> u64 {
+        let table = &self.tables[hash_index];
+        let mut res = 0;
+        for (i, byte_table) in table.iter().enumerate() {
+            res ^= byte_table[((x >> (8 * i)) & 0xff) as usize];
+        }
+        res
+    }
+}
+
+/// All hash families, with num_hashes functions each.
+/// The reference family (sha256) is first.
+pub fn hash_families(num_hashes: usize) -> Vec<Box<dyn HashFamily>> {
+    vec![
+        Box::new(Sha256Family),
+        Box::new(SipHashFamily),
+        Box::new(MultiplyShiftFamily::new(num_hashes, HASH_FAMILY_SEED)),
+        Box::new(TabulationFamily::new(num_hashes, HASH_FAMILY_SEED)),
+    ]
+}
+
+/// Get a hash family with num_hashes functions by its name.
+pub fn hash_family_by_name(name: &str, num_hashes: usize) -> Option<Box<dyn HashFamily>> {
+    hash_families(num_hashes).into_iter()
+        .find(|hash_family| hash_family.name() == name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use gen_elems;
+    use std::hash::{Hasher};
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_siphash24_matches_std() {
+        for &(k0, k1, x) in &[(0, 0, 0), (1, 2, 3), (7, HASH_FAMILY_SEED, u64::max_value())] {
+            let mut hasher = ::std::hash::SipHasher::new_with_keys(k0, k1);
+            hasher.write(&x.to_le_bytes());
+            assert_eq!(siphash24(k0, k1, x), hasher.finish());
+        }
+    }
+
+    #[test]
+    fn test_hash_families_consistent() {
+        for hash_family in hash_families(4) {
+            assert_eq!(hash_family.hash(3, 5), hash_family.hash(3, 5));
+            assert_ne!(hash_family.hash(3, 5), hash_family.hash(3, 6));
+            assert_ne!(hash_family.hash(2, 5), hash_family.hash(3, 5));
+        }
+        assert!(hash_family_by_name("tabulation", 4).is_some());
+        assert!(hash_family_by_name("unknown", 4).is_none());
+    }
+
+    /// Kolmogorov-Smirnov statistic of two samples.
+    fn ks_statistic(sample_a: &[f64], sample_b: &[f64]) -> f64 {
+        let mut a = sample_a.to_vec();
+        let mut b = sample_b.to_vec();
+        a.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        b.sort_by(|x, y| x.partial_cmp(y).unwrap());
+
+        let (mut i, mut j) = (0, 0);
+        let mut max_diff: f64 = 0.0;
+        while i < a.len() && j < b.len() {
+            if a[i] <= b[j] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+            let diff = (i as f64) / (a.len() as f64) - (j as f64) / (b.len() as f64);
+            max_diff = max_diff.max(diff.abs());
+        }
+        max_diff
+    }
+
+    /// Normalized minimum of every hash function over elems: n * min / 2^64.
+    /// For a good family this is approximately exponentially distributed with mean 1.
+    fn normalized_mins(hash_family: &dyn HashFamily, elems: &[u64], num_hashes: usize) -> Vec<f64> {
+        (0 .. num_hashes)
+            .map(|hash_index| elems.iter()
+                 .map(|&x| hash_family.hash(hash_index, x))
+                 .min().unwrap())
+            .map(|m| (elems.len() as f64) * (m as f64) / (u64::max_value() as f64))
+            .collect::<Vec<f64>>()
+    }
+
+    #[test]
+    fn test_min_distributions_match() {
+        let seed: &[_] = &[1,2,3,4];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+
+        let num_hashes = 300;
+        let random_elems = gen_elems(1000, &mut rng);
+        // Consecutive elements are harder for weak families:
+        let consecutive_elems = (0 .. 1000).collect::<Vec<u64>>();
+
+        for elems in &[random_elems, consecutive_elems] {
+            let reference = normalized_mins(&Sha256Family, elems, num_hashes);
+            for hash_family in hash_families(num_hashes) {
+                let mins = normalized_mins(hash_family.as_ref(), elems, num_hashes);
+                let mean = mins.iter().sum::<f64>() / (num_hashes as f64);
+                // Critical value of the two sample test, for significance level 0.001:
+                let critical = 1.95 * (2.0 / (num_hashes as f64)).sqrt();
+                let ks = ks_statistic(&mins, &reference);
+                assert!((mean - 1.0).abs() < 0.25, "{}: mean={}", hash_family.name(), mean);
+                assert!(ks < critical, "{}: ks={}", hash_family.name(), ks);
+            }
+        }
+    }
+}
@@ -1,4 +1,8 @@
 pub mod approx_funcs;
+pub mod gossip;
+pub mod estimators;
+pub mod eval_report;
+pub mod hash_family;
 
 extern crate rand;
 extern crate bincode;
@@ -7,9 +11,12 @@ extern crate ring;
 use self::rand::{Rng};
 use self::ring::{digest};
 use bincode::{serialize, Infinite};
-use std::ops;
+use std::collections::HashMap;
 
 use approx_funcs::{ApproxFunc};
+use estimators::{SizeEstimator, MinsEstimator};
+use eval_report::{ErrorReport, error_report};
+use hash_family::{HashFamily, Sha256Family};
 
 
 /// Generate random u64 elements:
@@ -44,23 +51,54 @@ fn hash_elem(hash_index: usize, x: u64) -> u64 {
 }
 
 
-fn calc_mins(elems: &Vec<u64>, num_hashes: usize) -> Vec<u64> {
+fn calc_mins(elems: &[u64], num_hashes: usize, hash_family: &dyn HashFamily) -> Vec<u64> {
     (0 .. num_hashes)
         .map(|hash_index| elems.iter()
-             .map(|&x| hash_elem(hash_index, x))
+             .map(|&x| hash_family.hash(hash_index, x))
              .min().unwrap())
         .collect::<Vec<u64>>()
 }
 
-fn square_dist<T: Copy + ops::Sub<Output=T> 
-                        + ops::Mul<Output=T> 
-                        + PartialOrd 
-                        + Ord>(x:T, y:T) -> T {
-    if x > y {
-        (x - y) * (x - y)
-    } else {
-        (y - x) * (y - x)
+/// Results of evaluating a size estimator.
+#[derive(Debug, Clone)]
+pub struct EstimatorEval {
+    /// Summary of the relative errors of the estimations.
+    pub report: ErrorReport,
+    /// Amount of bytes used by the sketch of one estimate.
+    pub memory_bytes: usize,
+}
+
+/// Evaluate size estimators.
+/// Every iteration a random set of num_elems elements is generated, and every estimator
+/// estimates its size from a sketch of num_regs registers, using functions of hash_family.
+pub fn eval_size_estimators<R: Rng>(num_iters: usize,
+                           num_regs: usize,
+                           num_elems: usize,
+                           estimators: &[&dyn SizeEstimator],
+                           hash_family: &dyn HashFamily,
+                           rng: &mut R) -> Vec<EstimatorEval> {
+
+    let mut rel_errs = vec![Vec::new(); estimators.len()];
+    for _ in 0 .. num_iters {
+        let elems = gen_elems(num_elems, rng);
+        // Estimators with the same kind of sketch share the sketch:
+        let mut sketches: HashMap<&str, Vec<u64>> = HashMap::new();
+        for (i, &estimator) in estimators.iter().enumerate() {
+            let sketch = sketches.entry(estimator.sketch_name())
+                .or_insert_with(|| estimator.sketch(&elems, num_regs, hash_family));
+            let approx_size = estimator.estimate(sketch) as f64;
+            let size = elems.len() as f64;
+            rel_errs[i].push((approx_size - size) / size);
+        }
     }
+
+    rel_errs.iter()
+        .zip(estimators.iter())
+        .map(|(estimator_rel_errs, &estimator)| EstimatorEval {
+            report: error_report(estimator_rel_errs, rng),
+            memory_bytes: num_regs * estimator.register_bytes(),
+        })
+        .collect::<Vec<EstimatorEval>>()
 }
 
 
@@ -72,20 +110,16 @@ pub fn eval_approx_size_funcs<R: Rng>(num_iters: usize,
                            approx_size_funcs: &[&ApproxFunc],
                            rng: &mut R) -> Vec<f64> {
 
-    let mut total_serrors = vec![0; approx_size_funcs.len()];
-    for _ in 0 .. num_iters {
-        let elems = gen_elems(num_elems, rng);
-        let mins = calc_mins(&elems, num_mins);
-        for (i, &approx_size_func) in approx_size_funcs.iter().enumerate() {
-            let approx_size = approx_size_func(&mins);
-            total_serrors[i] += square_dist(approx_size, elems.len());
-        }
-
-    }
+    let mins_estimators = approx_size_funcs.iter()
+        .map(|&approx_func| MinsEstimator {name: "", approx_func})
+        .collect::<Vec<MinsEstimator>>();
+    let estimators = mins_estimators.iter()
+        .map(|estimator| estimator as &dyn SizeEstimator)
+        .collect::<Vec<&dyn SizeEstimator>>();
 
-    total_serrors.into_iter()
-        .map(|te| te / num_iters)
-        .map(|variance| (variance as f64).sqrt() / (num_elems as f64))
+    eval_size_estimators(num_iters, num_mins, num_elems, &estimators, &Sha256Family, rng)
+        .into_iter()
+        .map(|estimator_eval| estimator_eval.report.err_ratio)
         .collect::<Vec<f64>>()
 }
 
@@ -113,7 +147,7 @@ mod tests {
         let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
         let elems = gen_elems(20, &mut rng);
         assert_eq!(elems.len(), 20);
-        let mins = calc_mins(&elems, 4);
+        let mins = calc_mins(&elems, 4, &Sha256Family);
         assert_eq!(mins.len(), 4);
     }
 
@@ -134,4 +168,24 @@ mod tests {
         assert_eq!(err_ratios.len(),1);
     }
 
+    #[test]
+    fn test_eval_size_estimators() {
+        let seed: &[_] = &[1,2,3,4];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+
+        let estimators = estimators::size_estimators();
+        let estimators_refs = estimators.iter()
+            .map(|estimator| estimator.as_ref())
+            .collect::<Vec<&dyn SizeEstimator>>();
+        let hash_family = hash_family::SipHashFamily;
+        let evals = eval_size_estimators(3, 16, 200, &estimators_refs, &hash_family, &mut rng);
+        assert_eq!(evals.len(), estimators.len());
+        for (estimator, eval) in estimators.iter().zip(evals.iter()) {
+            assert_eq!(eval.memory_bytes, 16 * estimator.register_bytes());
+            assert!(eval.report.err_ratio >= 0.0);
+            let (low, high) = eval.report.err_ratio_ci;
+            assert!(low <= high);
+        }
+    }
+
 }
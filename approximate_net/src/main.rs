@@ -1,11 +1,15 @@
 extern crate rand;
 extern crate approximate_net;
 
+use std::env;
+
 use self::rand::{StdRng};
 use approximate_net::{
-    eval_approx_size_funcs};
+    eval_size_estimators};
 
-use approximate_net::approx_funcs::{APPROX_FUNCS_NAMED, ApproxFunc};
+use approximate_net::estimators::{size_estimators, SizeEstimator};
+use approximate_net::eval_report::{ERR_QUANTILES};
+use approximate_net::hash_family::{hash_family_by_name};
 
 fn main() {
     let seed: &[_] = &[1,2,3,4,5,6];
@@ -14,26 +18,37 @@ fn main() {
     let num_iters = 100;
     let num_mins = 40;
     let num_elems = 1000000;
-    println!("Calculating error ratios for approximation functions...");
+    // Hash family may be given as a command line argument (Default: sha256):
+    let hash_family_name = env::args().nth(1).unwrap_or_else(|| "sha256".to_string());
+    let hash_family = hash_family_by_name(&hash_family_name, num_mins)
+        .unwrap_or_else(|| panic!("Unknown hash family: {}", hash_family_name));
+    println!("Calculating error ratios for size estimators...");
     println!("num_iters = {}",num_iters);
     println!("num_mins  = {}",num_mins);
     println!("num_elems = {}",num_elems);
+    println!("hash_family = {}",hash_family.name());
     println!();
 
-    let approx_funcs = APPROX_FUNCS_NAMED.iter()
-        .map(|&(approx_func, _)| approx_func)
-        .collect::<Vec<&ApproxFunc>>();
-
-    let err_ratios = eval_approx_size_funcs(num_iters, 
-                                            num_mins, 
-                                            num_elems, 
-                                            approx_funcs.as_slice(),
-                                            &mut rng);
-
-    println!("err_ratio for approximation functions:");
-    for (i,&(_, func_name)) in APPROX_FUNCS_NAMED.iter().enumerate() {
-        println!("{:30} : {}", func_name, err_ratios[i]);
-
+    let estimators = size_estimators();
+    let estimators_refs = estimators.iter()
+        .map(|estimator| estimator.as_ref())
+        .collect::<Vec<&dyn SizeEstimator>>();
+
+    let evals = eval_size_estimators(num_iters, 
+                                     num_mins, 
+                                     num_elems, 
+                                     estimators_refs.as_slice(),
+                                     hash_family.as_ref(),
+                                     &mut rng);
+
+    println!("Relative error reports for size estimators:");
+    println!("quantiles = {:?}", ERR_QUANTILES);
+    for (estimator, eval) in estimators.iter().zip(evals.iter()) {
+        let report = &eval.report;
+        println!("{:30} : {:6} bytes : err_ratio={:.4} ci=[{:.4}, {:.4}] bias={:+.4} quantiles={:+.3?}",
+                 estimator.name(), eval.memory_bytes, report.err_ratio,
+                 report.err_ratio_ci.0, report.err_ratio_ci.1, report.bias,
+                 report.err_quantiles);
     }
 
 }
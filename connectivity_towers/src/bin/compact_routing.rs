@@ -0,0 +1,114 @@
+/*
+ * Compare Thorup-Zwick style compact routing (Stretch at most 3) with landmarks routing
+ * using backtracking (find_path_landmarks_dfs) and using random jumps (find_path_landmarks).
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::chord::{RingKey};
+use net_coords::router::{Router};
+use net_coords::landmarks::{find_path_landmarks};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::greedy_dfs::{LandmarksDfsRouter};
+use net_coords::landmarks::compact_routing::{build_compact_routing, compact_state_sizes};
+use net_coords::state_size::{state_size_stats};
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let num_pairs = 500;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Comparing compact routing, backtracking and random jumps landmarks routing");
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 11 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            print!("g={:2}; {:8}; ", g, kind.name());
+
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+            let num_nodes = net.igraph.node_count();
+
+            // Thorup-Zwick: About sqrt(n * ln(n)) landmarks:
+            let num_compact_landmarks = ((num_nodes as f64) * (num_nodes as f64).ln()).sqrt() as usize;
+            let compact_landmarks = selector.select(&net, num_compact_landmarks, &mut network_rng);
+            let routing = build_compact_routing(&net, &compact_landmarks);
+
+            let num_landmarks = g * g;
+            let amount_close = g * g;
+            let landmarks = selector.select(&net, num_landmarks, &mut network_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut routing_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let node_range: Range<usize> = Range::new(0, num_nodes);
+
+            let dfs_router = LandmarksDfsRouter {coords: &coords, landmarks: &landmarks};
+            let routers: Vec<&dyn Router<RingKey>> = vec![&routing, &dfs_router];
+
+            // For every router: (found, sum of stretch, max stretch)
+            let mut router_stats = vec![(0, 0.0_f64, 0.0_f64); routers.len()];
+            let mut rw_found = 0;
+            let mut rw_sum_stretch: f64 = 0.0;
+
+            let mut num_routed = 0;
+            while num_routed < num_pairs {
+                let src_node = node_range.ind_sample(&mut routing_rng);
+                let dst_node = node_range.ind_sample(&mut routing_rng);
+                if src_node == dst_node {
+                    continue;
+                }
+                num_routed += 1;
+                let dist = net.dist(src_node, dst_node).unwrap() as f64;
+
+                for (router, stats) in routers.iter().zip(router_stats.iter_mut()) {
+                    if let Some(route) = router.route(src_node, dst_node, &net) {
+                        let stretch = (route.weight as f64) / dist;
+                        stats.0 += 1;
+                        stats.1 += stretch;
+                        stats.2 = stats.2.max(stretch);
+                    }
+                }
+
+                if let Some(length) = find_path_landmarks(src_node, dst_node, amount_close,
+                                        &net, &coords, &landmarks, &mut routing_rng) {
+                    rw_found += 1;
+                    rw_sum_stretch += (length as f64) / dist;
+                }
+            }
+
+            let sizes = state_size_stats(&compact_state_sizes(&routing));
+            print!("compact: nl={:4} entries={:8.2}/{:6} | ", num_compact_landmarks,
+                   sizes.entries.mean, sizes.entries.max);
+            for (router, &(found, sum_stretch, max_stretch)) in routers.iter().zip(&router_stats) {
+                print!("{}: found={:4} stretch={:5.3} max_stretch={:5.3} | ", router.name(),
+                       found, sum_stretch / (found as f64), max_stretch);
+            }
+            print!("rw: nl={:4} found={:4} stretch={:5.3}",
+                   num_landmarks, rw_found, rw_sum_stretch / (rw_found as f64));
+            println!();
+        }
+        println!();
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * Trace the convergence of the iterative algorithms (chord fingers, landmarks coordinates
+ * and local towers info) over various networks.
+ * Every iteration is written as a line to stdout, to allow plotting convergence curves.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use std::io;
+use rand::{StdRng};
+
+use net_coords::network_gen::{gen_network};
+use net_coords::chord::{init_fingers, converge_fingers_observed};
+use net_coords::landmarks::coords::{build_coords_observed};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::towers::{choose_towers, calc_towers_info_observed};
+use net_coords::convergence::{WriteRecorder};
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Tracing convergence of iterative algorithms");
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 15 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for net_type in 0 .. net_types { // Iterate over type of network
+            let net_name = match net_type {
+                0 => "rand",
+                1 => "2d",
+                2 => "rand+2d",
+                3 => "planar",
+                4 => "tree",
+                _ => unreachable!(),
+            };
+
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network(net_type, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            let stdout = io::stdout();
+
+            /* Chord fingers */
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut chord_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let mut fingers = init_fingers(&net, l, &mut chord_rng);
+            let label = format!("g={:2}; {:8}; chord ", g, net_name);
+            converge_fingers_observed(&net, &mut fingers, l,
+                                      &mut WriteRecorder::new(&label, stdout.lock()));
+
+            /* Landmarks coordinates */
+            let seed: &[_] = &[experiment_seed,3,g,net_type];
+            let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let num_landmarks = g * g;
+            let landmarks = selector.select(&net, num_landmarks, &mut landmarks_rng);
+            let label = format!("g={:2}; {:8}; coords", g, net_name);
+            build_coords_observed(&net, &landmarks,
+                                  &mut WriteRecorder::new(&label, stdout.lock())).unwrap();
+
+            /* Local towers */
+            let seed: &[_] = &[experiment_seed,4,g,net_type];
+            let mut towers_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let num_colors = g*2;
+            let total_num_towers = (2_u64.pow(g as u32) as f64).sqrt() as usize;
+            let num_towers = 1 + (total_num_towers / num_colors);
+            let chosen_towers = choose_towers(&net, num_towers, num_colors, &mut towers_rng);
+            let label = format!("g={:2}; {:8}; towers", g, net_name);
+            calc_towers_info_observed(&net, &chosen_towers,
+                                      &mut WriteRecorder::new(&label, stdout.lock()));
+
+            println!();
+        }
+    }
+}
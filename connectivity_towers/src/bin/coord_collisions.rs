@@ -0,0 +1,86 @@
+/*
+ * Measure collisions of landmarks coordinates (Nodes that share the same coordinate),
+ * and check that extending coordinates with a hashed node id suffix gives unique addresses.
+ * Also measure how often greedy routing by addresses delivers a message.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::landmarks::coords::{build_coords, collision_report};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::addressing::{build_addresses, is_address_unique,
+    route_to_address};
+
+
+#[cfg(not(test))]
+fn main() {
+    let max_groups = 3;
+    let num_pairs = 200;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Measuring landmarks coordinates collisions");
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 14 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for (kind_index, kind) in NetworkKind::all().into_iter().enumerate() {
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,kind_index];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            for &num_landmarks in &[g, g * g] {
+                print!("g={:2}; {:8}; nl={:4} |", g, kind.name(), num_landmarks);
+
+                let seed: &[_] = &[experiment_seed,2,g,kind_index,num_landmarks];
+                let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let landmarks = selector.select(&net, num_landmarks, &mut landmarks_rng);
+                let coords = build_coords(&net, &landmarks).unwrap();
+
+                let report = collision_report(&net, &coords, max_groups);
+                print!(" colliding={:6}/{:6} |", report.num_colliding_nodes(),
+                       net.igraph.node_count());
+                print!(" hist={:?} |", report.multiplicity_hist.iter()
+                       .filter(|&(&multiplicity, _)| multiplicity > 1)
+                       .collect::<Vec<_>>());
+                print!(" largest={:?} |", report.largest_groups.iter()
+                       .map(|group| (group.nodes.len(), group.max_dist()))
+                       .collect::<Vec<_>>());
+
+                let addresses = build_addresses(&net, &coords);
+                print!(" addresses_unique={} |", is_address_unique(&addresses));
+
+                let seed: &[_] = &[experiment_seed,3,g,kind_index,num_landmarks];
+                let mut routing_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+                let num_delivered = (0 .. num_pairs)
+                    .filter(|_| {
+                        let src_index = node_range.ind_sample(&mut routing_rng);
+                        let dst_index = node_range.ind_sample(&mut routing_rng);
+                        let path = route_to_address(src_index, &addresses[dst_index],
+                                                    &net, &addresses);
+                        *path.last().unwrap() == dst_index
+                    })
+                    .count();
+                print!(" greedy_delivered={:6.4}", (num_delivered as f64) / (num_pairs as f64));
+
+                println!();
+            }
+        }
+        println!();
+    }
+}
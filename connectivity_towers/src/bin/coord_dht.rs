@@ -0,0 +1,77 @@
+/*
+ * Distributed Post Office over landmarks coordinates:
+ * Keys are hashed into random coordinates and stored at the node reached by routing
+ * towards the coordinate. Measure lookup success rate, path lengths and load balance.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::landmarks::{gen_areas};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::coord_dht::{CoordDht, eval_coord_dht};
+use net_coords::landmarks::coord_randomizers::{RwDirectionalRandomizer};
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let max_visits = 2;
+    let num_lookups = 4;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Measuring landmarks coordinates DHT");
+    println!("landmark_selector = {}", selector.name());
+    println!("max_visits = {}", max_visits);
+    println!();
+
+    for g in 6 .. 11 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        let num_nodes = 1 << g;
+        // On average 4 keys per node:
+        let num_keys = 4 * num_nodes;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            print!("g={:2}; {:8}; ", g, kind.name());
+
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            // Large weights are required for random walk coordinates:
+            let net = gen_network_kind(kind, g, l, 0x10000, 0x20000, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            let num_landmarks = g;
+            let amount_close = g * g;
+            let landmarks = selector.select(&net, num_landmarks, &mut network_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+            let areas = gen_areas(amount_close, &net);
+
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut dht_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let randomizer = RwDirectionalRandomizer;
+            let mut dht = CoordDht::new(&net, &coords, &landmarks, &areas, &randomizer,
+                                        max_visits, experiment_seed as u64);
+            let eval = eval_coord_dht(&mut dht, num_keys, num_lookups, &mut dht_rng);
+
+            print!("success={:.3} | ", eval.lookup_success_rate);
+            print!("store_len={:10.1} lookup_len={:10.1} | ",
+                   eval.avg_store_path_len, eval.avg_lookup_path_len);
+            print!("load: mean={:6.2} p99={:4} max={:4} max_ratio={:6.2}",
+                   eval.load.mean, eval.load.p99, eval.load.max, eval.max_load_ratio);
+            println!();
+        }
+        println!();
+    }
+}
@@ -0,0 +1,83 @@
+/*
+ * Find out how balanced are the methods for generating a randomly looking coordinate
+ * in the network: Map random coordinates to their closest nodes and count repeats.
+ *
+ * Usage: coord_randomizers [randomizer_name ...]
+ * If no names are given, all randomizers are evaluated.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::coord_randomizers::{CoordRandomizer, CoordSpace,
+    coord_randomizers, coord_randomizer_by_name, eval_balance};
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 4;
+    // We generate num_nodes * iter_mult random coordinates:
+    let iter_mult = 1;
+    let experiment_seed = 0x1337;
+    let (selector, names) = landmark_selector_from_args(env::args().skip(1));
+    let randomizers: Vec<Box<dyn CoordRandomizer>> = if names.is_empty() {
+        coord_randomizers()
+    } else {
+        names.iter()
+            .map(|name| coord_randomizer_by_name(name)
+                 .unwrap_or_else(|| panic!("Unknown randomizer: {}", name)))
+            .collect()
+    };
+
+    println!("iter_mult = {}", iter_mult);
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 12 { // Iterate over size of network.
+        let l = 2 * g + 1;
+
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            // Large weights are required for random walk coordinates:
+            let net = gen_network_kind(kind, g, l, 0x10000, 0x20000, &mut network_rng);
+
+            // Amount of landmarks can not be above half of the node count:
+            let mut num_landmarks: usize = g * g;
+            if num_landmarks > net.igraph.node_count() / 2 {
+                num_landmarks = net.igraph.node_count() / 2;
+            }
+            let landmarks = selector.select(&net, num_landmarks, &mut network_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+            let space = CoordSpace::new(&landmarks, &coords);
+
+            for (randomizer_index, randomizer) in randomizers.iter().enumerate() {
+                print!("g={:2}; {:8}; ", g, kind.name());
+                print!("{:16}|", randomizer.name());
+
+                let seed: &[_] = &[experiment_seed,2,g,net_type,randomizer_index];
+                let mut balance_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let eval = eval_balance(&**randomizer, &space,
+                                        net.igraph.node_count() * iter_mult, &mut balance_rng);
+
+                print!(" max_nr = {:4}", eval.max_nr);
+                print!(" | average_min_indices = {:8.3}", eval.average_min_indices);
+                print!(" | unreached = {:5.3}", eval.unreached_frac);
+                println!();
+            }
+        }
+        println!();
+    }
+}
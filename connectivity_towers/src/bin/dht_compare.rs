@@ -0,0 +1,99 @@
+/*
+ * Compare the chord (ring distance) virtual DHT with the kademlia (XOR distance)
+ * virtual DHT over identical networks.
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::chord;
+use net_coords::kademlia;
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let net_iters = 2;
+    let num_pairs = 1000;
+    let bucket_size = 1;
+    let experiment_seed = 0x1337;
+
+    println!("Comparing chord and kademlia virtual DHTs");
+    println!("bucket_size = {}", bucket_size);
+    println!();
+
+    for g in 6 .. 13 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            for net_iter in 0 .. net_iters { // Two iterations for each type of network
+                print!("g={:2}; {:8}; ", g, kind.name());
+                print!("ni={:1} |",net_iter);
+
+                /* Generate network */
+                let seed: &[_] = &[experiment_seed,1,g,net_type,net_iter];
+                let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+                // Makes sure that the resulting network is connected:
+                assert!(net.is_connected());
+
+                /* Chord */
+                let seed: &[_] = &[experiment_seed,2,g,net_type,net_iter];
+                let mut chord_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let mut fingers = chord::init_fingers(&net, l, &mut chord_rng);
+                chord::converge_fingers(&net, &mut fingers, l);
+                let chord_optimal = chord::verify_global_optimality(&net, &fingers);
+                let semi_chains = chord::create_semi_chains(&net, &fingers);
+
+                /* Kademlia */
+                let seed: &[_] = &[experiment_seed,3,g,net_type,net_iter];
+                let mut kademlia_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let mut buckets = kademlia::init_buckets(&net, bucket_size, l, &mut kademlia_rng);
+                kademlia::converge_buckets(&net, &mut buckets);
+                let kademlia_optimal = kademlia::verify_global_optimality(&net, &buckets);
+
+                /* Route between the same random pairs of nodes */
+                let seed: &[_] = &[experiment_seed,4,g,net_type,net_iter];
+                let mut pairs_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+
+                let mut chord_found = 0;
+                let mut chord_sum_length = 0;
+                let mut kademlia_found = 0;
+                let mut kademlia_sum_length = 0;
+
+                for _ in 0 .. num_pairs {
+                    let src_id = net.index_to_node(node_range.ind_sample(&mut pairs_rng)).unwrap().clone();
+                    let dst_id = net.index_to_node(node_range.ind_sample(&mut pairs_rng)).unwrap().clone();
+
+                    if let Some(length) = chord::find_path(src_id, dst_id, &net, &semi_chains) {
+                        chord_found += 1;
+                        chord_sum_length += length;
+                    }
+                    if let Some(length) = kademlia::find_path(src_id, dst_id, &net, &buckets) {
+                        kademlia_found += 1;
+                        kademlia_sum_length += length;
+                    }
+                }
+
+                let avg_length = |sum_length: usize, found: usize|
+                    (sum_length as f64) / (found as f64);
+
+                print!(" chord: opt={} found={:4}/{:4} avg_len={:8.3} |",
+                       if chord_optimal {"V"} else {"X"},
+                       chord_found, num_pairs, avg_length(chord_sum_length, chord_found));
+                print!(" kademlia: opt={} found={:4}/{:4} avg_len={:8.3}",
+                       if kademlia_optimal {"V"} else {"X"},
+                       kademlia_found, num_pairs, avg_length(kademlia_sum_length, kademlia_found));
+
+                println!();
+            }
+        }
+        println!();
+    }
+}
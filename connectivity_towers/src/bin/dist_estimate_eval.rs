@@ -0,0 +1,66 @@
+/*
+ * Evaluate how well every distance mapper from coord_mappers tracks the real
+ * distance between nodes, over all kinds of networks.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::dist_eval::{sample_pairs, eval_all_mappers};
+
+
+#[cfg(not(test))]
+fn main() {
+    let num_pairs = 1000;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Evaluating distance estimates of coordinate mappers");
+    println!("landmark_selector = {}", selector.name());
+    println!("rel_err: mean/median/p90/max | bounds violations: below/above");
+    println!();
+
+    for g in 6 .. 12 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for (kind_index, kind) in NetworkKind::all().into_iter().enumerate() {
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,kind_index];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            let seed: &[_] = &[experiment_seed,2,g,kind_index];
+            let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let num_landmarks = g * g;
+            let landmarks = selector.select(&net, num_landmarks, &mut landmarks_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+
+            let seed: &[_] = &[experiment_seed,3,g,kind_index];
+            let mut pairs_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let pairs = sample_pairs(&net, num_pairs, &mut pairs_rng);
+
+            for eval in eval_all_mappers(&pairs, &coords, &landmarks) {
+                print!("g={:2}; {:8}; {:12}|", g, kind.name(), eval.name);
+                print!(" rel_err={:10.4}/{:10.4}/{:10.4}/{:10.4} |",
+                       eval.mean_rel_error, eval.median_rel_error,
+                       eval.p90_rel_error, eval.max_rel_error);
+                print!(" bounds={:6.4}/{:6.4} |", eval.below_lower_frac.unwrap(),
+                       eval.above_upper_frac.unwrap());
+                print!(" pearson={:7.4} spearman={:7.4}", eval.pearson, eval.spearman);
+                println!();
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,71 @@
+/*
+ * Kill a fraction of the landmarks after the coordinates have converged, and measure
+ * how routing with find_path_landmarks degrades: Right after the failure (Nodes only forget
+ * the entries of the failed landmarks), and after computing the coordinates again.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+
+use net_coords::network_gen::{gen_network};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::failures::{simulate_landmark_failures, RoutingStats};
+
+
+#[cfg(not(test))]
+fn print_stats(name: &str, stats: &RoutingStats) {
+    print!(" {}: {:.3} {:7.2} |", name, stats.success_rate(), stats.avg_length);
+}
+
+#[cfg(not(test))]
+fn main() {
+    let num_pairs = 100;
+    let net_types = 5;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Routing with failed landmarks");
+    println!("landmark_selector = {}", selector.name());
+    println!("Each column: success rate, average route length");
+    println!();
+
+    for g in 6 .. 12 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        let num_landmarks = g * g;
+        let amount_close = g * g;
+        for net_type in 0 .. net_types {
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network(net_type, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let landmarks = selector.select(&net, num_landmarks, &mut landmarks_rng);
+
+            for (frac_index, &fail_frac) in [0.1, 0.25, 0.5].iter().enumerate() {
+                print!("g={:2}; nt={}; fail={:.2} |", g, net_type, fail_frac);
+
+                let seed: &[_] = &[experiment_seed,3,g,net_type,frac_index];
+                let mut fail_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let report = simulate_landmark_failures(&net, &landmarks, fail_frac,
+                                amount_close, num_pairs, &mut fail_rng);
+
+                print_stats("intact", &report.intact);
+                print_stats("stale", &report.stale);
+                print_stats("recomputed", &report.recomputed);
+                println!();
+            }
+        }
+        println!();
+    }
+}
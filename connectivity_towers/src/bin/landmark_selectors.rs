@@ -0,0 +1,142 @@
+/*
+ * Compare landmark selection strategies: Routing success and stretch using landmarks
+ * coordinates, and balance of random coordinates mapped to nodes.
+ * Strategies may be given as command line arguments (Default: all).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network::{Network};
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::chord::{RingKey};
+use net_coords::landmarks::{gen_areas, find_path_landmarks_areas};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::randomize_coord::{randomize_coord_rw_directional,
+    calc_upper_constraints};
+use net_coords::landmarks::coord_mappers::{max_dist};
+use net_coords::landmarks::selectors::{LandmarkSelector, landmark_selectors,
+    landmark_selector_by_name};
+
+
+/// Route between random pairs of nodes using landmarks chosen by selector.
+/// Returns (success_rate, average_stretch)
+fn routing_experiment(selector: &dyn LandmarkSelector<RingKey>, net: &Network<RingKey>,
+                      num_landmarks: usize, amount_close: usize, num_pairs: usize,
+                      rng: &mut StdRng) -> (f64, f64) {
+
+    let landmarks = selector.select(net, num_landmarks, rng);
+    let coords = build_coords(net, &landmarks).unwrap();
+    let areas = gen_areas(amount_close, net);
+    let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+
+    let mut num_found: usize = 0;
+    let mut sum_stretch: f64 = 0.0;
+    for _ in 0 .. num_pairs {
+        let src_node = node_range.ind_sample(rng);
+        let dst_node = node_range.ind_sample(rng);
+        if src_node == dst_node {
+            continue;
+        }
+        if let Some(path_len) = find_path_landmarks_areas(src_node, dst_node,
+                                            net, &coords, &landmarks, &areas, rng) {
+            num_found += 1;
+            // Areas distances ignore weights, so stretch is measured in hops:
+            let (_, hops, _) = net.closest_nodes_structure(src_node)
+                .find(|&(index, _, _)| index == dst_node)
+                .unwrap();
+            sum_stretch += (path_len as f64) / (hops as f64);
+        }
+    }
+    ((num_found as f64) / (num_pairs as f64), sum_stretch / (num_found as f64))
+}
+
+/// Map random coordinates to their closest nodes, using landmarks chosen by selector.
+/// Returns the maximum amount of random coordinates mapped to a single node.
+fn balance_experiment(selector: &dyn LandmarkSelector<RingKey>, net: &Network<RingKey>,
+                      num_landmarks: usize, rng: &mut StdRng) -> usize {
+
+    let landmarks = selector.select(net, num_landmarks, rng);
+    let coords = build_coords(net, &landmarks).unwrap();
+    let upper_constraints = calc_upper_constraints(&landmarks, &coords);
+
+    let mut node_repeats: Vec<usize> = vec![0; net.igraph.node_count()];
+    for _ in 0 .. net.igraph.node_count() {
+        let rcoord = randomize_coord_rw_directional(&upper_constraints,
+                                                    &landmarks, &coords, rng);
+        let closest_node_index = (0 .. coords.len())
+            .min_by_key(|&i| (max_dist(&rcoord, &coords[i]), i))
+            .unwrap();
+        node_repeats[closest_node_index] += 1;
+    }
+    node_repeats.into_iter().max().unwrap()
+}
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let num_pairs = 200;
+    let experiment_seed = 0x1337;
+
+    let names = env::args().skip(1).collect::<Vec<String>>();
+    let selectors: Vec<Box<dyn LandmarkSelector<RingKey>>> = if names.is_empty() {
+        landmark_selectors()
+    } else {
+        names.iter()
+            .map(|name| landmark_selector_by_name(name)
+                 .unwrap_or_else(|| panic!("Unknown landmark selector: {}", name)))
+            .collect()
+    };
+
+    println!("Comparing landmark selection strategies");
+    println!("success rate, average stretch | max node repeats");
+    println!();
+
+    for g in 6 .. 10 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            // Large weights are required for random walk coordinates:
+            let net = gen_network_kind(kind, g, l, 0x10000, 0x20000, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            let mut num_landmarks = g * g;
+            // Amount of landmarks can not be above half of the node count:
+            if num_landmarks > net.igraph.node_count() / 2 {
+                num_landmarks = net.igraph.node_count() / 2;
+            }
+            let amount_close = g * g;
+
+            for selector in &selectors {
+                print!("g={:2}; {:8}; ", g, kind.name());
+                print!("{:12}|", selector.name());
+
+                let seed: &[_] = &[experiment_seed,2,g,net_type];
+                let mut routing_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let (success_rate, avg_stretch) = routing_experiment(&**selector, &net,
+                         num_landmarks, amount_close, num_pairs, &mut routing_rng);
+                print!(" success={:6.4} stretch={:7.4} |", success_rate, avg_stretch);
+
+                let seed: &[_] = &[experiment_seed,3,g,net_type];
+                let mut balance_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let max_node_repeats = balance_experiment(&**selector, &net,
+                                                          num_landmarks, &mut balance_rng);
+                print!(" max_nr={:4}", max_node_repeats);
+
+                println!();
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,99 @@
+/*
+ * Compare landmarks routing using random jumps (find_path_landmarks) with
+ * greedy routing with backtracking (find_path_landmarks_dfs).
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::landmarks::{find_path_landmarks};
+use net_coords::landmarks::greedy_dfs::{find_path_landmarks_dfs};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let net_iters = 2;
+    let num_pairs = 500;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Comparing random jumps and backtracking landmarks routing");
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 14 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            for net_iter in 0 .. net_iters { // Two iterations for each type of network
+                print!("g={:2}; {:8}; ", g, kind.name());
+                print!("ni={:1} |",net_iter);
+
+                /* Generate network */
+                let seed: &[_] = &[experiment_seed,1,g,net_type,net_iter];
+                let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+                // Makes sure that the resulting network is connected:
+                assert!(net.is_connected());
+
+                let num_landmarks = g * g;
+                let amount_close = g * g;
+                let landmarks = selector.select(&net, num_landmarks, &mut network_rng);
+                let coords = build_coords(&net, &landmarks).unwrap();
+
+                let seed: &[_] = &[experiment_seed,2,g,net_type,net_iter];
+                let mut routing_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+
+                let mut rw_found = 0;
+                let mut rw_sum_length: u64 = 0;
+                let mut dfs_found = 0;
+                let mut dfs_sum_length: usize = 0;
+                let mut dfs_sum_backtracks: usize = 0;
+                let mut dfs_max_header_bytes: usize = 0;
+
+                for _ in 0 .. num_pairs {
+                    let src_node = node_range.ind_sample(&mut routing_rng);
+                    let dst_node = node_range.ind_sample(&mut routing_rng);
+
+                    if let Some(length) = find_path_landmarks(src_node, dst_node, amount_close,
+                                            &net, &coords, &landmarks, &mut routing_rng) {
+                        rw_found += 1;
+                        rw_sum_length += length;
+                    }
+
+                    if let Some(route) = find_path_landmarks_dfs(src_node, dst_node,
+                                                             &net, &coords, &landmarks) {
+                        dfs_found += 1;
+                        dfs_sum_length += route.hops;
+                        dfs_sum_backtracks += route.backtracks;
+                        dfs_max_header_bytes = dfs_max_header_bytes.max(route.max_header_bytes());
+                    }
+                }
+
+                print!(" rw: found={:4}/{:4} avg_len={:8.3} |", rw_found, num_pairs,
+                       (rw_sum_length as f64) / (rw_found as f64));
+                print!(" dfs: found={:4}/{:4} avg_len={:8.3} avg_backtracks={:8.3} \
+                       max_header_bytes={:8}", dfs_found, num_pairs,
+                       (dfs_sum_length as f64) / (dfs_found as f64),
+                       (dfs_sum_backtracks as f64) / (dfs_found as f64),
+                       dfs_max_header_bytes);
+
+                println!();
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * Measure the per-node routing state of the virtual DHTs (chord, kademlia),
+ * the landmarks scheme (coordinate + area) and local towers, as a function of g.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::chord;
+use net_coords::kademlia;
+use net_coords::landmarks::{gen_areas, areas_state_sizes};
+use net_coords::landmarks::coords::{build_coords, coords_state_sizes};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::towers::{choose_towers, calc_towers_info, towers_state_sizes};
+use net_coords::state_size::{NodeStateSize, nodes_state_sizes, state_size_stats};
+
+
+/// Print distribution statistics of state sizes for one scheme.
+fn print_sizes(name: &str, sizes: &Vec<NodeStateSize>) {
+    let stats = state_size_stats(sizes);
+    print!(" {}: entries={:9.2}/{:6}/{:6} bytes={:10.2}/{:8}/{:8} |", name,
+           stats.entries.mean, stats.entries.max, stats.entries.p99,
+           stats.bytes.mean, stats.bytes.max, stats.bytes.p99);
+}
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let bucket_size = 1;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Measuring per-node routing state");
+    println!("landmark_selector = {}", selector.name());
+    println!("Every scheme is printed as mean/max/p99 across nodes");
+    println!();
+
+    for g in 6 .. 14 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            print!("g={:2}; {:8}; ", g, kind.name());
+            print!("|");
+
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            /* Chord fingers and semi chains arrays */
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut chord_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let mut fingers = chord::init_fingers(&net, l, &mut chord_rng);
+            chord::converge_fingers(&net, &mut fingers, l);
+            print_sizes("chord", &nodes_state_sizes(&fingers));
+            let semi_chains = chord::create_semi_chains(&net, &fingers);
+            print_sizes("schains", &nodes_state_sizes(&semi_chains));
+
+            /* Kademlia buckets */
+            let seed: &[_] = &[experiment_seed,3,g,net_type];
+            let mut kademlia_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let mut buckets = kademlia::init_buckets(&net, bucket_size, l, &mut kademlia_rng);
+            kademlia::converge_buckets(&net, &mut buckets);
+            print_sizes("kademlia", &nodes_state_sizes(&buckets));
+
+            /* Landmarks: coordinate and area */
+            let seed: &[_] = &[experiment_seed,4,g,net_type];
+            let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let num_landmarks = g * g;
+            let amount_close = g * g;
+            let landmarks = selector.select(&net, num_landmarks, &mut landmarks_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+            let areas = gen_areas(amount_close, &net);
+            let landmarks_sizes = coords_state_sizes(&coords).iter()
+                .zip(areas_state_sizes(&areas).iter())
+                .map(|(coord_size, area_size)| coord_size.add(area_size))
+                .collect::<Vec<NodeStateSize>>();
+            print_sizes("landmarks", &landmarks_sizes);
+
+            /* Local towers */
+            let seed: &[_] = &[experiment_seed,5,g,net_type];
+            let mut towers_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let num_colors = g*2;
+            let total_num_towers = (2_u64.pow(g as u32) as f64).sqrt() as usize;
+            let num_towers = 1 + (total_num_towers / num_colors);
+            let chosen_towers = choose_towers(&net, num_towers, num_colors, &mut towers_rng);
+            let towers_info = calc_towers_info(&net, &chosen_towers);
+            print_sizes("towers", &towers_state_sizes(&towers_info));
+
+            println!();
+        }
+        println!();
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * Check how keeping the k closest towers of every color affects strong connectivity of the
+ * local towers overlay graph (See towers_scc) when there are few towers, and how well
+ * routing over the overlay tolerates tower failures, and failures of regular nodes
+ * (Using the alternative gateways of the entries).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::collections::HashSet;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::random_util::{choose_k_nums};
+use net_coords::towers::{choose_towers, overlay_connectivity, TowersDist};
+use net_coords::towers::k_closest::{calc_towers_info_k, drop_failed_towers,
+                                    towers_overlay_k, avg_distinct_gateways, alt_gateway_frac};
+use net_coords::towers::routing::{find_path_towers_k, find_path_towers_k_avoiding};
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let num_colors = 2;
+    let max_k = 4;
+    let fail_frac = 0.2;
+    let relay_fail_frac = 0.05;
+    let num_pairs = 200;
+    let experiment_seed = 0x1337;
+
+    println!("Strong connectivity and tower failures with k closest towers");
+    println!("num_colors = {}, fail_frac = {}, relay_fail_frac = {}", 
+             num_colors, fail_frac, relay_fail_frac);
+    println!();
+
+    for g in 6 .. 12 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            /* Choose towers, and towers that will fail */
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut towers_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            // Few towers of every color:
+            let total_num_towers = (2_u64.pow(g as u32) as f64).sqrt() as usize;
+            let num_towers = 1 + (total_num_towers / (2 * num_colors));
+            let chosen_towers = choose_towers(&net, num_towers, num_colors, &mut towers_rng);
+            let mut all_towers = chosen_towers.iter()
+                .flat_map(|ctowers| ctowers.iter().cloned())
+                .collect::<HashSet<usize>>()
+                .into_iter()
+                .collect::<Vec<usize>>();
+            // Sort for determinism:
+            all_towers.sort();
+            let num_failed = ((all_towers.len() as f64) * fail_frac) as usize;
+            let failed_towers = choose_k_nums(num_failed, all_towers.len(), &mut towers_rng)
+                .into_iter()
+                .map(|i| all_towers[i])
+                .collect::<HashSet<usize>>();
+            let no_failures = HashSet::new();
+
+            // Regular nodes that will fail:
+            let relays = (0 .. net.igraph.node_count())
+                .filter(|node| !all_towers.contains(node))
+                .collect::<Vec<usize>>();
+            let num_failed_relays = ((relays.len() as f64) * relay_fail_frac) as usize;
+            let failed_relays = choose_k_nums(num_failed_relays, relays.len(), &mut towers_rng)
+                .into_iter()
+                .map(|i| relays[i])
+                .collect::<HashSet<usize>>();
+            let alive_nodes = (0 .. net.igraph.node_count())
+                .filter(|node| !failed_relays.contains(node))
+                .collect::<Vec<usize>>();
+
+            for k in 1 .. max_k + 1 {
+                print!("g={:2}; {:8}; ", g, kind.name());
+                print!("num_towers={:3} k={:1} |", num_towers, k);
+
+                let towers_info_k = calc_towers_info_k(&net, &chosen_towers, k,
+                                                       TowersDist::Hops);
+                print!(" gateways={:5.2} alt={:4.2} |", avg_distinct_gateways(&towers_info_k),
+                       alt_gateway_frac(&towers_info_k));
+
+                let overlay = towers_overlay_k(&chosen_towers, &towers_info_k, &no_failures);
+                let (_, sconnected) = overlay_connectivity(&overlay);
+                print!(" sconnected={} |", if sconnected {"V"} else {"X"});
+
+                /* Fail some towers */
+                let towers_info_failed = drop_failed_towers(&towers_info_k, &failed_towers);
+                let overlay_failed = towers_overlay_k(&chosen_towers, &towers_info_failed,
+                                                      &failed_towers);
+                let (_, sconnected_failed) = overlay_connectivity(&overlay_failed);
+                print!(" failed: sconnected={} |", if sconnected_failed {"V"} else {"X"});
+
+                let seed: &[_] = &[experiment_seed,3,g,net_type];
+                let mut pairs_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+                let mut num_found = 0;
+                let mut sum_stretch = 0.0;
+                for _ in 0 .. num_pairs {
+                    let src_node = node_range.ind_sample(&mut pairs_rng);
+                    let dst_node = node_range.ind_sample(&mut pairs_rng);
+                    if let Some(route) = find_path_towers_k(src_node, dst_node, &net,
+                                                   &towers_info_failed, &overlay_failed) {
+                        num_found += 1;
+                        if src_node != dst_node {
+                            let dist = net.dist(src_node, dst_node).unwrap();
+                            sum_stretch += (route.weight as f64) / (dist as f64);
+                        } else {
+                            sum_stretch += 1.0;
+                        }
+                    }
+                }
+                print!(" found={:4}/{:4} stretch={:7.3} |", num_found, num_pairs,
+                       sum_stretch / (num_found as f64));
+
+                /* Fail some regular nodes */
+                let seed: &[_] = &[experiment_seed,4,g,net_type];
+                let mut pairs_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let alive_range: Range<usize> = Range::new(0, alive_nodes.len());
+                let mut num_found_primary = 0;
+                let mut num_found_alt = 0;
+                for _ in 0 .. num_pairs {
+                    let src_node = alive_nodes[alive_range.ind_sample(&mut pairs_rng)];
+                    let dst_node = alive_nodes[alive_range.ind_sample(&mut pairs_rng)];
+                    // Using only the main gateways:
+                    if let Some(route) = find_path_towers_k(src_node, dst_node, &net,
+                                                   &towers_info_k, &overlay) {
+                        if route.path.iter().all(|node| !failed_relays.contains(node)) {
+                            num_found_primary += 1;
+                        }
+                    }
+                    if find_path_towers_k_avoiding(src_node, dst_node, &net, &towers_info_k,
+                                                   &overlay, &failed_relays).is_some() {
+                        num_found_alt += 1;
+                    }
+                }
+                print!(" relays failed: found={:4} found_alt={:4}", 
+                       num_found_primary, num_found_alt);
+                println!();
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * Route messages over the local towers overlay, and compare success rate and stretch with
+ * chord and landmarks routing over the same networks.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::chord;
+use net_coords::landmarks::{find_path_landmarks};
+use net_coords::landmarks::coords::{build_coords};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::towers::{choose_towers, calc_towers_info, towers_overlay, is_connected};
+use net_coords::towers::routing::{find_path_towers};
+
+
+/// Accumulated routing results of one routing method.
+struct RoutingResults {
+    found: usize,
+    sum_stretch: f64,
+}
+
+impl RoutingResults {
+    fn new() -> Self {
+        RoutingResults {
+            found: 0,
+            sum_stretch: 0.0,
+        }
+    }
+
+    fn add(&mut self, length: Option<u64>, dist: u64) {
+        if let Some(length) = length {
+            self.found += 1;
+            self.sum_stretch += (length as f64) / (dist as f64);
+        }
+    }
+
+    fn print(&self, name: &str, num_pairs: usize) {
+        print!(" {}: found={:4}/{:4} stretch={:7.3} |", name, self.found, num_pairs,
+               self.sum_stretch / (self.found as f64));
+    }
+}
+
+
+#[cfg(not(test))]
+fn main() {
+    let net_types = 5;
+    let num_pairs = 500;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Comparing towers, chord and landmarks routing");
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 11 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for kind in NetworkKind::all().into_iter().take(net_types) { // Iterate over type of network
+            let net_type = kind.net_type();
+            print!("g={:2}; {:8}; ", g, kind.name());
+
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,net_type];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            /* Towers */
+            let seed: &[_] = &[experiment_seed,2,g,net_type];
+            let mut towers_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let num_colors = g*2;
+            let total_num_towers = (2_u64.pow(g as u32) as f64).sqrt() as usize;
+            let num_towers = 1 + (total_num_towers / num_colors);
+            let chosen_towers = choose_towers(&net, num_towers, num_colors, &mut towers_rng);
+            let towers_info = calc_towers_info(&net, &chosen_towers);
+            let overlay = towers_overlay(&chosen_towers, &towers_info);
+            let (_, sconnected) = is_connected(&chosen_towers, &towers_info);
+            print!("sconnected={} |", if sconnected {"V"} else {"X"});
+
+            /* Chord */
+            let seed: &[_] = &[experiment_seed,3,g,net_type];
+            let mut chord_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let mut fingers = chord::init_fingers(&net, l, &mut chord_rng);
+            chord::converge_fingers(&net, &mut fingers, l);
+            let semi_chains = chord::create_semi_chains(&net, &fingers);
+
+            /* Landmarks */
+            let seed: &[_] = &[experiment_seed,4,g,net_type];
+            let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let landmarks = selector.select(&net, g * g, &mut landmarks_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+            let amount_close = g * g;
+
+            /* Route between the same random pairs of nodes */
+            let seed: &[_] = &[experiment_seed,5,g,net_type];
+            let mut pairs_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+
+            let mut towers_results = RoutingResults::new();
+            let mut chord_results = RoutingResults::new();
+            let mut landmarks_results = RoutingResults::new();
+
+            let mut num_routed = 0;
+            while num_routed < num_pairs {
+                let src_node = node_range.ind_sample(&mut pairs_rng);
+                let dst_node = node_range.ind_sample(&mut pairs_rng);
+                if src_node == dst_node {
+                    continue;
+                }
+                num_routed += 1;
+                let dist = net.dist(src_node, dst_node).unwrap();
+
+                towers_results.add(find_path_towers(src_node, dst_node, &net,
+                                                    &towers_info, &overlay)
+                                   .map(|route| route.weight), dist);
+
+                let src_id = net.index_to_node(src_node).unwrap().clone();
+                let dst_id = net.index_to_node(dst_node).unwrap().clone();
+                chord_results.add(chord::find_path(src_id, dst_id, &net, &semi_chains)
+                                  .map(|length| length as u64), dist);
+
+                landmarks_results.add(find_path_landmarks(src_node, dst_node, amount_close,
+                                        &net, &coords, &landmarks, &mut pairs_rng), dist);
+            }
+
+            towers_results.print("towers", num_pairs);
+            chord_results.print("chord", num_pairs);
+            landmarks_results.print("landmarks", num_pairs);
+            println!();
+        }
+        println!();
+    }
+}
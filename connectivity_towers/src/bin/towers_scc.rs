@@ -1,6 +1,8 @@
 /*
  * Check if the local towers connections form a 
  * strongly connected directed overlay graph in various networks.
+ * Every node keeps the k closest towers of every color, for a few values of k.
+ * Tower placement strategies may be given as command line arguments (Default: all).
  */
 
 #![cfg(not(test))]
@@ -8,21 +10,38 @@ extern crate net_coords;
 extern crate rand;
 extern crate ordered_float;
 
+use std::env;
+use std::collections::HashSet;
+
 use rand::{StdRng};
 
 use net_coords::network_gen::{gen_network};
-use net_coords::towers::{choose_towers, 
-                         calc_towers_info, 
-                         is_connected,
-                         is_towers_info_filled};
+use net_coords::towers::{calc_towers_info, 
+                         towers_overlay,
+                         is_towers_info_filled,
+                         TowersDist};
+use net_coords::towers::k_closest::{calc_towers_info_k, towers_overlay_k};
+use net_coords::towers::overlay_report::{overlay_report};
+use net_coords::towers::placement::{TowerPlacement, tower_placements, tower_placement_by_name};
 
 
 #[cfg(not(test))]
 fn main() {
     let net_types = 5;
     let net_iters = 2;
+    let ks = [1, 2, 3];
     let experiment_seed = 0x1337;
 
+    let names = env::args().skip(1).collect::<Vec<String>>();
+    let placements: Vec<Box<dyn TowerPlacement<u64>>> = if names.is_empty() {
+        tower_placements()
+    } else {
+        names.iter()
+            .map(|name| tower_placement_by_name(name)
+                 .unwrap_or_else(|| panic!("Unknown placement: {}", name)))
+            .collect()
+    };
+
     println!("Checking if local towers overlay graph is strongly connected");
     println!();
 
@@ -30,17 +49,6 @@ fn main() {
         let l = 2 * g + 1;
         for net_type in 0 .. net_types { // Iterate over type of network
             for net_iter in 0 .. net_iters { // Three iterations for each type of network
-                print!("g={:2}; ",g);
-                match net_type {
-                    0 => print!("rand    ; "),
-                    1 => print!("2d      ; "),
-                    2 => print!("rand+2d ; "),
-                    3 => print!("planar  ; "),
-                    4 => print!("tree    ; "),
-                    _ => unreachable!(),
-                }
-                print!("ni={:1} |",net_iter);
-
                 /* Generate network */
                 let seed: &[_] = &[experiment_seed,1,g,net_type,net_iter];
                 let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
@@ -48,37 +56,81 @@ fn main() {
                 // Makes sure that the resulting network is connected:
                 assert!(net.is_connected());
 
-                // Choose towers:
-                let seed: &[_] = &[experiment_seed,2,g,net_type,net_iter];
-                let mut towers_rng: StdRng = rand::SeedableRng::from_seed(seed);
-                let num_colors = g*2;
-                let total_num_towers = (2_u64.pow(g as u32) as f64).sqrt() as usize;
-                let num_towers = 1 + (total_num_towers / num_colors);
-                let chosen_towers = choose_towers(&net, num_towers, num_colors, &mut towers_rng);
-                // let chosen_towers = choose_towers(&net, 1, num_colors, &mut towers_rng); // Sanity check
-                let towers_info = calc_towers_info(&net, &chosen_towers);
-                // Make sure that towers_info are valid:
-                assert!(is_towers_info_filled(&towers_info));
-
-                print!("num_colors = {:5} |", num_colors);
-                print!("num_towers = {:5} |", num_towers);
-
-                let (connected, sconnected) = is_connected(&chosen_towers, &towers_info);
-                print!(" connected = ");
-                if connected {
-                    print!("V");
-                } else {
-                    print!("X");
+                for placement in &placements {
+                    // Choose towers:
+                    let seed: &[_] = &[experiment_seed,2,g,net_type,net_iter];
+                    let mut towers_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                    let num_colors = g*2;
+                    let total_num_towers = (2_u64.pow(g as u32) as f64).sqrt() as usize;
+                    let num_towers = 1 + (total_num_towers / num_colors);
+                    let chosen_towers = placement.place(&net, num_towers, num_colors, 
+                                                        &mut towers_rng);
+                    // Sanity check:
+                    // let chosen_towers = placement.place(&net, 1, num_colors, &mut towers_rng);
+
+                    for &k in &ks {
+                        print!("g={:2}; ",g);
+                        match net_type {
+                            0 => print!("rand    ; "),
+                            1 => print!("2d      ; "),
+                            2 => print!("rand+2d ; "),
+                            3 => print!("planar  ; "),
+                            4 => print!("tree    ; "),
+                            _ => unreachable!(),
+                        }
+                        print!("ni={:1} |",net_iter);
+                        print!("{:7}|", placement.name());
+
+                        let overlay = if k == 1 {
+                            let towers_info = calc_towers_info(&net, &chosen_towers);
+                            // Make sure that towers_info are valid:
+                            assert!(is_towers_info_filled(&towers_info));
+                            towers_overlay(&chosen_towers, &towers_info)
+                        } else {
+                            let towers_info_k = calc_towers_info_k(&net, &chosen_towers, k,
+                                                                   TowersDist::Hops);
+                            towers_overlay_k(&chosen_towers, &towers_info_k, &HashSet::new())
+                        };
+
+                        print!("num_colors = {:5} |", num_colors);
+                        print!("num_towers = {:5} |", num_towers);
+                        print!("k = {:1} |", k);
+
+                        let report = overlay_report(&chosen_towers, &overlay);
+                        print!(" connected = ");
+                        if report.connected {
+                            print!("V");
+                        } else {
+                            print!("X");
+                        }
+
+                        print!(" | sconnected = ");
+                        if report.strongly_connected {
+                            print!("V");
+                        } else {
+                            print!("X");
+                        }
+
+                        print!(" | sccs = {:4} max_scc = {:5}", 
+                               report.num_sccs, report.scc_sizes.max);
+                        print!(" | in_deg: mean={:5.2} max={:3}", 
+                               report.in_degree.mean, report.in_degree.max);
+                        print!(" | out_deg: mean={:5.2} max={:3}", 
+                               report.out_degree.mean, report.out_degree.max);
+                        match report.diameter {
+                            Some(diameter) => print!(" | diameter = {:3}", diameter),
+                            None => print!(" | diameter =   -"),
+                        }
+                        print!(" | dag_depth = {:3}", report.condensation_depth);
+
+                        if !report.strongly_connected {
+                            print!(" | isolated_towers = {:?}", report.isolated_towers);
+                            print!(" | isolated_colors = {:?}", report.isolated_colors);
+                        }
+
+                        println!();
+                    }
                 }
-
-                print!(" | sconnected = ");
-                if sconnected {
-                    print!("V");
-                } else {
-                    print!("X");
-                }
-
-                println!();
             }
         }
         println!();
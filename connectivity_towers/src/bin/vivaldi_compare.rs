@@ -0,0 +1,112 @@
+/*
+ * Compare landmarks coordinates with low dimensional vivaldi coordinates:
+ * Size of coordinates, quality of distance estimates and routing success.
+ * Landmark selection strategy may be given as a command line argument:
+ * --selector=<name> (Default: random).
+ */
+
+#![cfg(not(test))]
+extern crate net_coords;
+extern crate rand;
+
+use std::env;
+
+use rand::{StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use net_coords::network::{Network};
+use net_coords::network_gen::{gen_network_kind, NetworkKind};
+use net_coords::chord::{RingKey};
+use net_coords::landmarks::{find_path_by_dist};
+use net_coords::landmarks::greedy_dfs::{find_path_dfs};
+use net_coords::landmarks::coords::{build_coords, coords_state_sizes};
+use net_coords::landmarks::selectors::{landmark_selector_from_args};
+use net_coords::landmarks::coord_mappers::{approx_max_dist};
+use net_coords::landmarks::dist_eval::{sample_pairs, eval_dist_fn, NodePair};
+use net_coords::vivaldi::{VivaldiParams, build_vivaldi_coords, approx_vivaldi_dist,
+    vivaldi_state_sizes};
+use net_coords::state_size::{NodeStateSize, state_size_stats};
+
+
+/// Evaluate one kind of coordinates, given as an approximate distance function, and print
+/// the results.
+fn eval_coords<F>(name: &'static str, node_dist: F, sizes: &Vec<NodeStateSize>,
+                  net: &Network<RingKey>, pairs: &Vec<NodePair>, amount_close: usize,
+                  rng: &mut StdRng) where F: Fn(usize, usize) -> f64 {
+
+    let stats = state_size_stats(sizes);
+    print!(" {}: bytes={:6} |", name, stats.bytes.max);
+
+    let eval = eval_dist_fn(name, &node_dist, pairs, None);
+    print!(" rel_err={:8.4} spearman={:7.4} |", eval.median_rel_error, eval.spearman);
+
+    let node_range: Range<usize> = Range::new(0, net.igraph.node_count());
+    let num_pairs = pairs.len();
+    let mut rw_found = 0;
+    let mut dfs_sum_hops = 0;
+    for _ in 0 .. num_pairs {
+        let src_node = node_range.ind_sample(rng);
+        let dst_node = node_range.ind_sample(rng);
+        if find_path_by_dist(src_node, dst_node, amount_close, net, &node_dist, rng).is_some() {
+            rw_found += 1;
+        }
+        dfs_sum_hops += find_path_dfs(src_node, dst_node, net, &node_dist).unwrap().hops;
+    }
+    print!(" rw_found={:4}/{:4} dfs_avg_hops={:8.3} |", rw_found, num_pairs,
+           (dfs_sum_hops as f64) / (num_pairs as f64));
+}
+
+
+#[cfg(not(test))]
+fn main() {
+    let num_pairs = 300;
+    let experiment_seed = 0x1337;
+    let (selector, _) = landmark_selector_from_args(env::args().skip(1));
+
+    println!("Comparing landmarks coordinates with vivaldi coordinates");
+    println!("landmark_selector = {}", selector.name());
+    println!();
+
+    for g in 6 .. 12 { // Iterate over size of network.
+        let l = 2 * g + 1;
+        for (kind_index, kind) in NetworkKind::all().into_iter().enumerate() {
+            print!("g={:2}; {:8}|", g, kind.name());
+
+            /* Generate network */
+            let seed: &[_] = &[experiment_seed,1,g,kind_index];
+            let mut network_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let net = gen_network_kind(kind, g, l, 1, 2, &mut network_rng);
+            // Makes sure that the resulting network is connected:
+            assert!(net.is_connected());
+
+            let seed: &[_] = &[experiment_seed,2,g,kind_index];
+            let mut pairs_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let pairs = sample_pairs(&net, num_pairs, &mut pairs_rng);
+            let amount_close = g * g;
+
+            /* Landmarks coordinates */
+            let seed: &[_] = &[experiment_seed,3,g,kind_index];
+            let mut landmarks_rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let landmarks = selector.select(&net, g * g, &mut landmarks_rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+            eval_coords("landmarks", |x, y| approx_max_dist(x, y, &coords, &landmarks) as f64,
+                        &coords_state_sizes(&coords), &net, &pairs, amount_close,
+                        &mut landmarks_rng);
+
+            /* Vivaldi coordinates */
+            for &(name, dims, use_height) in &[("viv2", 2, false), ("viv2h", 2, true),
+                                               ("viv5h", 5, true)] {
+                let seed: &[_] = &[experiment_seed,4,g,kind_index,dims];
+                let mut vivaldi_rng: StdRng = rand::SeedableRng::from_seed(seed);
+                let params = VivaldiParams::new(dims, use_height);
+                let vcoords = build_vivaldi_coords(&net, &params, &mut vivaldi_rng);
+                eval_coords(name, |x, y| approx_vivaldi_dist(x, y, &vcoords),
+                            &vivaldi_state_sizes(&vcoords), &net, &pairs, amount_close,
+                            &mut vivaldi_rng);
+            }
+
+            println!();
+        }
+        println!();
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::{HashSet, HashMap};
+
+use network::{Network};
+use chord::{RingKey, vdist, add_cyc};
+use chord::ids_chain::{ids_chain};
+use chord::node_fingers::{NodeFingers, best_right_key, best_left_key};
+
+/// The reason a finger is maintained.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub enum FingerType {
+    Left,
+    PowerOfTwo,
+    NeighborConnector,
+    Random,
+}
+
+/// A finger that does not point to the globally best key.
+#[derive(Clone, Debug)]
+pub struct NonOptimalFinger {
+    pub node_id: RingKey,
+    pub target_id: RingKey,
+    pub final_id: RingKey,
+    pub best_id: RingKey,
+    /// Ring distance from target_id to final_id minus ring distance
+    /// from target_id to best_id.
+    pub dist_gap: RingKey,
+    pub finger_type: FingerType,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct FingerTypeCounts {
+    pub total: usize,
+    pub non_optimal: usize,
+}
+
+pub struct OptimalityReport {
+    pub non_optimal_fingers: Vec<NonOptimalFinger>,
+    pub type_counts: HashMap<FingerType, FingerTypeCounts>,
+}
+
+/// Classify right fingers of node x_i according to the reason they are maintained.
+/// Right target ids are generated in gen_right_target_ids. If a target id was generated for
+/// a few reasons, the first matching type is chosen (PowerOfTwo, NeighborConnector, Random).
+fn right_finger_type(x_i: usize, target_id: RingKey, net: &Network<RingKey>,
+                     l: usize) -> FingerType {
+
+    let x_id = net.index_to_node(x_i).unwrap().clone();
+
+    for i in 0 .. l {
+        let pow_val = 2_i64.pow(i as u32);
+        if target_id == add_cyc(x_id,pow_val,l) || target_id == add_cyc(x_id,-pow_val,l) {
+            return FingerType::PowerOfTwo;
+        }
+    }
+
+    let connector_ids = net.igraph.neighbors(x_i)
+        .flat_map(|neighbor_index| ids_chain(x_id,
+                          net.index_to_node(neighbor_index).unwrap().clone()))
+        .collect::<HashSet<RingKey>>();
+
+    if connector_ids.contains(&target_id) {
+        return FingerType::NeighborConnector;
+    }
+    FingerType::Random
+}
+
+/// Check every finger of every node against the globally best key possible.
+/// Returns a report of all non optimal fingers, and counts per finger type.
+pub fn optimality_report(net: &Network<RingKey>, fingers: &Vec<NodeFingers>,
+                         l: usize) -> OptimalityReport {
+
+    // Obtain a sorted vector of all keys in the network:
+    let mut all_keys: Vec<RingKey> = (0 .. net.igraph.node_count())
+        .map(|x_i| net.index_to_node(x_i).unwrap().clone())
+        .collect::<Vec<_>>();
+    all_keys.sort();
+
+    let mut report = OptimalityReport {
+        non_optimal_fingers: Vec::new(),
+        type_counts: HashMap::new(),
+    };
+
+    for x_i in 0 .. net.igraph.node_count() {
+        let x_id = net.index_to_node(x_i).unwrap().clone();
+
+        for fing in &fingers[x_i].left.sorted_fingers {
+            let best_id = best_left_key(fing.target_id, &all_keys);
+            let dist_gap = vdist(fing.schain.final_id, fing.target_id, l) -
+                vdist(best_id, fing.target_id, l);
+            report.add_finger(x_id, fing.target_id, fing.schain.final_id,
+                              best_id, dist_gap, FingerType::Left);
+        }
+
+        for fing in &fingers[x_i].right.sorted_fingers {
+            let best_id = best_right_key(fing.target_id, &all_keys);
+            let dist_gap = vdist(fing.target_id, fing.schain.final_id, l) -
+                vdist(fing.target_id, best_id, l);
+            let finger_type = right_finger_type(x_i, fing.target_id, net, l);
+            report.add_finger(x_id, fing.target_id, fing.schain.final_id,
+                              best_id, dist_gap, finger_type);
+        }
+    }
+
+    report
+}
+
+impl OptimalityReport {
+    pub fn is_optimal(&self) -> bool {
+        self.non_optimal_fingers.is_empty()
+    }
+
+    fn add_finger(&mut self, node_id: RingKey, target_id: RingKey, final_id: RingKey,
+                  best_id: RingKey, dist_gap: RingKey, finger_type: FingerType) {
+
+        let counts = self.type_counts.entry(finger_type)
+            .or_insert_with(FingerTypeCounts::default);
+        counts.total += 1;
+
+        if final_id == best_id {
+            return;
+        }
+
+        counts.non_optimal += 1;
+        self.non_optimal_fingers.push(NonOptimalFinger {
+            node_id,
+            target_id,
+            final_id,
+            best_id,
+            dist_gap,
+            finger_type,
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::random_net_chord;
+    use chord::{init_fingers, converge_fingers, verify_global_optimality};
+
+    #[test]
+    fn test_optimality_report_converged() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let l: usize = 10;
+        let net = random_net_chord(20,3,l,&mut rng);
+        let mut fingers = init_fingers(&net,l, &mut rng);
+        converge_fingers(&net, &mut fingers,l);
+        assert!(verify_global_optimality(&net, &fingers));
+
+        let report = optimality_report(&net, &fingers, l);
+        assert!(report.is_optimal());
+
+        let num_fingers: usize = fingers.iter()
+            .map(|nf| nf.left.sorted_fingers.len() + nf.right.sorted_fingers.len())
+            .sum();
+        let total_counted: usize = report.type_counts.values()
+            .map(|counts| counts.total)
+            .sum();
+        assert!(num_fingers == total_counted);
+        assert!(report.type_counts[&FingerType::Left].total == 20);
+        assert!(report.type_counts[&FingerType::PowerOfTwo].total > 0);
+    }
+
+    #[test]
+    fn test_optimality_report_not_converged() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let l: usize = 10;
+        let net = random_net_chord(20,3,l,&mut rng);
+        // Fingers were not converged, so they all point to the node itself:
+        let fingers = init_fingers(&net,l, &mut rng);
+        assert!(!verify_global_optimality(&net, &fingers));
+
+        let report = optimality_report(&net, &fingers, l);
+        assert!(!report.is_optimal());
+        for non_opt in &report.non_optimal_fingers {
+            assert!(non_opt.final_id == non_opt.node_id);
+            assert!(non_opt.final_id != non_opt.best_id);
+            assert!(non_opt.dist_gap > 0);
+        }
+        let total_non_optimal: usize = report.type_counts.values()
+            .map(|counts| counts.non_optimal)
+            .sum();
+        assert!(total_non_optimal == report.non_optimal_fingers.len());
+    }
+}
@@ -5,8 +5,11 @@ extern crate rand;
 pub mod ids_chain;
 pub mod semi_chains_array;
 pub mod node_fingers;
+pub mod diagnostics;
+pub mod physical_path;
 
 use std::collections::{HashSet};
+use std::time::Instant;
 
 use self::rand::{Rng};
 use self::rand::distributions::{IndependentSample, Range};
@@ -15,7 +18,9 @@ use network::{Network};
 use self::ids_chain::{ids_chain};
 use self::semi_chains_array::{SemiChainsArray};
 use self::node_fingers::{NodeFingers, SemiChain};
+use self::physical_path::{schain_nodes, install_next_hops, prune_next_hops};
 use index_pair::{index_pair, Pair};
+use convergence::{ConvergenceObserver, NullObserver, IterStats};
 
 
 pub type RingKey = u64; // A key in the chord ring
@@ -147,6 +152,15 @@ fn iter_fingers(net: &Network<RingKey>,
             }
 
 
+            // Physical nodes of the connecting chain (Only if next hops are recorded),
+            // and its first hop in both directions:
+            let x_remote_nodes = schain_nodes(x_i, &remote_schain, net, fingers);
+            let (x_remote_next_hop, remote_x_next_hop) = match x_remote_nodes {
+                Some(ref nodes) => (net.index_to_node(nodes[1]).cloned(),
+                                    net.index_to_node(nodes[nodes.len() - 2]).cloned()),
+                None => (None, None),
+            };
+
             // Get two mutable indices (x_i and remote_i):
             let (m_x_i, m_remote_i) = match index_pair(&mut fingers, x_i, remote_i) {
                 Pair::Two(m_x_i,m_remote_i) => (m_x_i, m_remote_i),
@@ -155,14 +169,22 @@ fn iter_fingers(net: &Network<RingKey>,
             
             // UpdateRequest:
             // Every finger of x_id will get all of x_id's fingers.
-            has_changed |= m_remote_i.update_by_fingers(&m_x_i, 
-                       remote_schain.length, l);
+            let remote_new_schains = m_remote_i.update_by_fingers_with_next_hop(&m_x_i, 
+                       remote_schain.length, remote_x_next_hop, l);
 
             // UpdateResponse:
             // x_id will get all of the fingers of his fingers
-            has_changed |= m_x_i.update_by_fingers(&m_remote_i,
-                        remote_schain.length, l);
-                
+            let x_new_schains = m_x_i.update_by_fingers_with_next_hop(&m_remote_i,
+                        remote_schain.length, x_remote_next_hop, l);
+
+            has_changed |= !remote_new_schains.is_empty() || !x_new_schains.is_empty();
+
+            // The nodes along the connecting chain forward the new chains:
+            if let Some(mut x_remote_nodes) = x_remote_nodes {
+                install_next_hops(&x_remote_nodes, &x_new_schains, net, fingers);
+                x_remote_nodes.reverse();
+                install_next_hops(&x_remote_nodes, &remote_new_schains, net, fingers);
+            }
         }
     }
 
@@ -172,7 +194,14 @@ fn iter_fingers(net: &Network<RingKey>,
 
 /// Get to converging state of fingers for all the network.
 pub fn converge_fingers(net: &Network<RingKey>, 
-             mut fingers: &mut Vec<NodeFingers>, l: usize) {
+             fingers: &mut Vec<NodeFingers>, l: usize) {
+    converge_fingers_observed(net, fingers, l, &mut NullObserver);
+}
+
+/// Get to converging state of fingers for all the network.
+/// observer is notified after every iteration.
+pub fn converge_fingers_observed<O: ConvergenceObserver>(net: &Network<RingKey>, 
+             mut fingers: &mut Vec<NodeFingers>, l: usize, observer: &mut O) {
 
     // First iteration: We insert all edges:
     for x_i in 0 .. net.igraph.node_count() {
@@ -184,16 +213,45 @@ pub fn converge_fingers(net: &Network<RingKey>,
                 final_id: neighbor_id,
                 length: 1,
             };
-            fingers[x_i].update(&schain,l);
+            fingers[x_i].update_with_next_hop(&schain, Some(neighbor_id), l);
         }
     }
 
-    // println!("Iterating fingers...");
-    // print!(".");
-    while iter_fingers(&net, &mut fingers, l) {
-        // print!(".");
+    // Reset counters, so that only work done during iterations is counted:
+    for nf in fingers.iter_mut() {
+        nf.take_stats();
+    }
+
+    let mut iter = 0;
+    loop {
+        let start = Instant::now();
+        let has_changed = iter_fingers(&net, &mut fingers, l);
+
+        let mut stats = IterStats {
+            iter,
+            nodes_changed: 0,
+            entries_changed: 0,
+            skipped_updates: 0,
+            elapsed: start.elapsed(),
+        };
+        for nf in fingers.iter_mut() {
+            let node_stats = nf.take_stats();
+            if node_stats.changed_fingers > 0 {
+                stats.nodes_changed += 1;
+            }
+            stats.entries_changed += node_stats.changed_fingers;
+            stats.skipped_updates += node_stats.skipped_updates;
+        }
+        observer.on_iter(&stats);
+
+        if !has_changed {
+            break;
+        }
+        iter += 1;
     }
-    // println!("");
+
+    // Forwarding state of chains that are no longer used by any finger is dropped:
+    prune_next_hops(net, fingers);
 }
 
 /// Make sure that every finger reaches the best globally key possible
@@ -243,8 +301,19 @@ pub fn create_semi_chains(net: &Network<RingKey>,
 pub fn find_path(src_id: RingKey, dst_id: RingKey, net: &Network<RingKey>, 
                  semi_chains: &Vec<SemiChainsArray>) -> Option<usize> {
 
+    find_route(src_id, dst_id, net, semi_chains)
+        .map(|route| route.iter().map(|schain| schain.length).sum())
+}
+
+/// Returns the semi chains used to get from src_id to dst_id, or
+/// None if no path was found.
+/// The first semi chain begins at src_id, and every next semi chain begins 
+/// at the final_id of the previous one.
+pub fn find_route(src_id: RingKey, dst_id: RingKey, net: &Network<RingKey>, 
+                 semi_chains: &Vec<SemiChainsArray>) -> Option<Vec<SemiChain>> {
+
     let mut cur_id = src_id;
-    let mut length: usize = 0;
+    let mut route: Vec<SemiChain> = Vec::new();
     while cur_id != dst_id {
         let cur_semi_chains = &semi_chains[net.node_to_index(&cur_id).unwrap()];
         let schain = cur_semi_chains.find_closest_left(dst_id);
@@ -252,10 +321,10 @@ pub fn find_path(src_id: RingKey, dst_id: RingKey, net: &Network<RingKey>,
             return None;
         }
 
-        length += schain.length;
         cur_id = schain.final_id;
+        route.push(schain.clone());
     }
-    Some(length)
+    Some(route)
 }
 
 #[cfg(test)]
@@ -263,6 +332,7 @@ mod tests {
     use super::*;
     use self::rand::{StdRng};
     use network_gen::random_net_chord;
+    use convergence::{TraceRecorder};
 
     #[test]
     fn test_d() {
@@ -313,6 +383,25 @@ mod tests {
 
 
 
+    #[test]
+    fn test_converge_fingers_observed() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let l: usize = 10;
+        let net = random_net_chord(20,3,l,&mut rng);
+        let mut fingers = init_fingers(&net,l, &mut rng);
+        let mut recorder = TraceRecorder::new();
+        converge_fingers_observed(&net, &mut fingers, l, &mut recorder);
+        assert!(verify_global_optimality(&net, &fingers));
+
+        // The last iteration is the one where nothing has changed:
+        let last_stats = recorder.trace.last().unwrap();
+        assert!(last_stats.entries_changed == 0);
+        assert!(last_stats.nodes_changed == 0);
+        assert!(recorder.trace[0].entries_changed > 0);
+        assert!(recorder.trace.iter().any(|stats| stats.skipped_updates > 0));
+    }
+
     #[test]
     fn test_chord_basic() {
         let seed: &[_] = &[1,2,3,4,9];
@@ -1,7 +1,9 @@
 extern crate itertools;
 
 use chord::{RingKey, vdist};
+use state_size::{StateSize, NodeStateSize};
 use std::collections::{HashSet, HashMap};
+use std::mem::size_of;
 
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
 pub struct SemiChain {
@@ -25,12 +27,26 @@ pub struct SortedFingersRight {
     pub sorted_fingers: Vec<Finger>,
 }
 
+/// Counters of work done by a node since the last time they were taken.
+#[derive(Clone, Default, Debug)]
+pub struct UpdateStats {
+    /// Amount of fingers that were changed.
+    pub changed_fingers: usize,
+    /// Amount of update_by_fingers calls that were skipped thanks to version caching.
+    pub skipped_updates: usize,
+}
+
 pub struct NodeFingers {
     id: RingKey,
     pub left: SortedFingersLeft,
     pub right: SortedFingersRight,
     version: usize, // Current version, used for caching.
     updated_by: HashMap<RingKey, usize>,
+    stats: UpdateStats,
+    /// Next hop node of every chain that goes through this node, if next hops recording
+    /// is enabled. The next hop of a chain (final_id, length) knows the chain
+    /// (final_id, length - 1).
+    next_hops: Option<HashMap<SemiChain, RingKey>>,
 }
 
 
@@ -48,12 +64,31 @@ fn is_left_finger_better(finger: &Finger, schain: &SemiChain, l:usize) -> bool {
     new_dist < cur_dist
 }
 
+/// Get the globally best key for a right finger with the given target_id:
+/// The first key clockwise from target_id.
+pub fn best_right_key(target_id: RingKey, sorted_keys: &Vec<RingKey>) -> RingKey {
+    match sorted_keys.binary_search(&target_id) {
+        Ok(index) => sorted_keys[index],
+        Err(index) => sorted_keys[index % sorted_keys.len()],
+    }
+}
+
+/// Get the globally best key for a left finger with the given target_id:
+/// The first key counter-clockwise from target_id.
+pub fn best_left_key(target_id: RingKey, sorted_keys: &Vec<RingKey>) -> RingKey {
+    match sorted_keys.binary_search(&target_id) {
+        Ok(index) => sorted_keys[index],
+        Err(index) => sorted_keys[
+            (index + sorted_keys.len() - 1) % sorted_keys.len()],
+    }
+}
+
 
 impl SortedFingersRight {
     /// Add a new known chain, possibly update some fingers to use a new chain.
-    /// Returns true if any chain was updated.
-    fn update(&mut self, schain: &SemiChain,l: usize, version: usize) -> bool {
-        let mut has_changed: bool = false;
+    /// Returns the amount of fingers that were updated.
+    fn update(&mut self, schain: &SemiChain,l: usize, version: usize) -> usize {
+        let mut num_changed: usize = 0;
 
         let fingers_len = self.sorted_fingers.len();
         // Find the last index where sorted_fingers[i].target_id <= schain.final_id:
@@ -67,21 +102,16 @@ impl SortedFingersRight {
         while is_right_finger_better(&self.sorted_fingers[cur_index], &schain, l) {
             self.sorted_fingers[cur_index].schain = schain.clone();
             self.sorted_fingers[cur_index].version = version;
-            has_changed = true;
+            num_changed += 1;
             cur_index = (cur_index + fingers_len - 1) % fingers_len;
         }
-        has_changed
+        num_changed
     }
 
     /// Check if chosen semi chains tips are optimal with respect to target_id.
     fn is_optimal(&self, sorted_keys: &Vec<RingKey>) -> bool {
         for fing in &self.sorted_fingers {
-            let best_key = match sorted_keys.binary_search(&fing.target_id) {
-                Ok(index) => sorted_keys[index],
-                Err(index) => sorted_keys[index % sorted_keys.len()],
-            };
-
-            if fing.schain.final_id != best_key {
+            if fing.schain.final_id != best_right_key(fing.target_id, sorted_keys) {
                 return false
             }
         }
@@ -92,9 +122,9 @@ impl SortedFingersRight {
 
 impl SortedFingersLeft {
     /// Add a new known chain, possibly update some fingers to use a new chain.
-    /// Returns true if any chain was updated.
-    fn update(&mut self, schain: &SemiChain,l: usize, version: usize) -> bool {
-        let mut has_changed: bool = false;
+    /// Returns the amount of fingers that were updated.
+    fn update(&mut self, schain: &SemiChain,l: usize, version: usize) -> usize {
+        let mut num_changed: usize = 0;
 
         let fingers_len = self.sorted_fingers.len();
         // Find the first index where sorted_fingers[i].target_id >= chain[0]:
@@ -109,22 +139,16 @@ impl SortedFingersLeft {
         while is_left_finger_better(&self.sorted_fingers[cur_index], &schain, l) {
             self.sorted_fingers[cur_index].schain = schain.clone();
             self.sorted_fingers[cur_index].version = version;
-            has_changed = true;
+            num_changed += 1;
             cur_index = (cur_index + 1) % fingers_len;
         }
-        has_changed
+        num_changed
     }
 
     /// Check if chosen semi chains tips are optimal with respect to target_id.
     fn is_optimal(&self, sorted_keys: &Vec<RingKey>) -> bool {
         for fing in &self.sorted_fingers {
-            let best_key = match sorted_keys.binary_search(&fing.target_id) {
-                Ok(index) => sorted_keys[index],
-                Err(index) => sorted_keys[
-                    (index + sorted_keys.len() - 1) % sorted_keys.len()],
-            };
-
-            if fing.schain.final_id != best_key {
+            if fing.schain.final_id != best_left_key(fing.target_id, sorted_keys) {
                 return false
             }
         }
@@ -143,6 +167,8 @@ impl NodeFingers {
             right: SortedFingersRight {sorted_fingers: Vec::new()},
             version: 0,
             updated_by: HashMap::new(),
+            stats: UpdateStats::default(),
+            next_hops: None,
         };
 
 
@@ -181,13 +207,59 @@ impl NodeFingers {
         nf
     }
 
+    /// Start recording next hops of chains.
+    pub fn enable_next_hops(&mut self) {
+        if self.next_hops.is_none() {
+            self.next_hops = Some(HashMap::new());
+        }
+    }
+
+    /// Get the recorded next hop of a chain.
+    /// A chain of length 0 has no next hop.
+    pub fn next_hop(&self, schain: &SemiChain) -> Option<RingKey> {
+        match self.next_hops {
+            Some(ref next_hops) => next_hops.get(schain).cloned(),
+            None => None,
+        }
+    }
+
+    /// Record the next hop of a chain that goes through this node, if next hops
+    /// recording is enabled. An already recorded next hop is kept.
+    pub fn set_next_hop(&mut self, schain: &SemiChain, next_hop: RingKey) {
+        if let Some(next_hops) = self.next_hops.as_mut() {
+            next_hops.entry(schain.clone()).or_insert(next_hop);
+        }
+    }
+
+    /// Forget the next hops of all chains not in keep.
+    pub fn retain_next_hops(&mut self, keep: &HashSet<SemiChain>) {
+        if let Some(next_hops) = self.next_hops.as_mut() {
+            next_hops.retain(|schain, _| keep.contains(schain));
+        }
+    }
+
     /// Add a new known chain, possibly updating existing fingers.
     /// Returns true if any finger was updated.
     pub fn update(&mut self, schain: &SemiChain, l: usize) -> bool {
-        let mut has_changed: bool = false;
+        self.update_with_next_hop(schain, None, l)
+    }
+
+    /// Add a new known chain together with its next hop, possibly updating existing
+    /// fingers. The next hop is recorded only if the chain is used by some finger.
+    /// Returns true if any finger was updated.
+    pub fn update_with_next_hop(&mut self, schain: &SemiChain, next_hop: Option<RingKey>,
+                            l: usize) -> bool {
         self.version += 1;
-        has_changed |= self.left.update(&schain, l, self.version);
-        has_changed |= self.right.update(&schain, l, self.version);
+        let num_changed = self.left.update(&schain, l, self.version) + 
+            self.right.update(&schain, l, self.version);
+        self.stats.changed_fingers += num_changed;
+        let has_changed = num_changed > 0;
+
+        if has_changed {
+            if let Some(next_hop) = next_hop {
+                self.set_next_hop(schain, next_hop);
+            }
+        }
 
         // Version is increased only if anything has changed:
         if !has_changed {
@@ -247,6 +319,15 @@ impl NodeFingers {
     /// Return if any finger in self has changed.
     pub fn update_by_fingers(&mut self, fingers_src: &NodeFingers, 
                  chain_length: usize, l:usize) -> bool {
+        !self.update_by_fingers_with_next_hop(fingers_src, chain_length, None, l).is_empty()
+    }
+
+    /// Update finger's struct by all fingers in fingers_src,
+    /// assuming that there is a connecting chain between the two
+    /// of length chain_length, whose first hop is next_hop.
+    /// Return the new chains that changed any finger in self.
+    pub fn update_by_fingers_with_next_hop(&mut self, fingers_src: &NodeFingers, 
+                 chain_length: usize, next_hop: Option<RingKey>, l:usize) -> Vec<SemiChain> {
 
         // Get last_version we have of fingers_src.
         // 0 is a reserved version number, which means we know nothing of fingers_src.
@@ -261,10 +342,11 @@ impl NodeFingers {
 
         if last_version == fingers_src.version {
             // We are already updated about this version of fingers_src.
-            return false;
+            self.stats.skipped_updates += 1;
+            return Vec::new();
         }
 
-        let mut has_changed = false; // Has any finger changed?
+        let mut new_schains = Vec::new(); // Chains that changed any finger.
 
         for Finger {schain, version, .. } in fingers_src.all_fingers() {
             if last_version >= version {
@@ -276,15 +358,41 @@ impl NodeFingers {
                 length: schain.length + chain_length,
             };
 
-            has_changed |= self.update(&new_schain, l);
+            if self.update_with_next_hop(&new_schain, next_hop, l) {
+                new_schains.push(new_schain);
+            }
         }
 
         // Update known version of fingers_src:
         self.updated_by.insert(fingers_src.id, fingers_src.version);
 
-        has_changed
+        new_schains
     }
 
+    /// Get the update counters of this node, and reset them.
+    pub fn take_stats(&mut self) -> UpdateStats {
+        let stats = self.stats.clone();
+        self.stats = UpdateStats::default();
+        stats
+    }
+}
+
+impl StateSize for NodeFingers {
+    /// Fingers, known versions of other nodes (updated_by) and recorded next hops (If enabled).
+    fn state_size(&self) -> NodeStateSize {
+        let num_fingers = self.left.sorted_fingers.len() + self.right.sorted_fingers.len();
+        let mut size = NodeStateSize {
+            entries: num_fingers + self.updated_by.len(),
+            bytes: num_fingers * size_of::<Finger>() +
+                self.updated_by.len() * (size_of::<RingKey>() + size_of::<usize>()),
+        };
+
+        if let Some(ref next_hops) = self.next_hops {
+            size.entries += next_hops.len();
+            size.bytes += next_hops.len() * (size_of::<SemiChain>() + size_of::<RingKey>());
+        }
+        size
+    }
 }
 
 
@@ -350,7 +458,7 @@ mod tests {
             final_id: 11,
             length: 4
         };
-        assert!(sfr.update(&sc, 7, 2));
+        assert!(sfr.update(&sc, 7, 2) == 2);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 11).unwrap().schain == sc);
     }
 
@@ -361,7 +469,7 @@ mod tests {
             final_id: 17,
             length: 4
         };
-        assert!(!sfr.update(&sc, 7, 4));
+        assert!(sfr.update(&sc, 7, 4) == 0);
     }
 
     #[test]
@@ -371,7 +479,7 @@ mod tests {
             final_id: 13,
             length: 4
         };
-        assert!(sfr.update(&sc, 7, 6));
+        assert!(sfr.update(&sc, 7, 6) == 3);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 11).unwrap().schain == sc);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 12).unwrap().schain == sc);
     }
@@ -383,7 +491,7 @@ mod tests {
             final_id: 2,
             length: 4
         };
-        assert!(sfr.update(&sc, 7, 8));
+        assert!(sfr.update(&sc, 7, 8) == 1);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 18).unwrap().schain == sc);
     }
 
@@ -445,7 +553,7 @@ mod tests {
             final_id: 12,
             length: 4
         };
-        assert!(sfr.update(&sc,7, 9));
+        assert!(sfr.update(&sc,7, 9) == 1);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 12).unwrap().schain == sc);
     }
 
@@ -456,7 +564,7 @@ mod tests {
             final_id: 8,
             length: 4
         };
-        assert!(!sfr.update(&sc, 7, 10));
+        assert!(sfr.update(&sc, 7, 10) == 0);
     }
 
     #[test]
@@ -466,7 +574,7 @@ mod tests {
             final_id: 10,
             length: 4
         };
-        assert!(sfr.update(&sc, 7, 11));
+        assert!(sfr.update(&sc, 7, 11) == 2);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 11).unwrap().schain == sc);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 12).unwrap().schain == sc);
     }
@@ -478,7 +586,7 @@ mod tests {
             final_id: 29,
             length: 4
         };
-        assert!(sfr.update(&sc, 7, 12));
+        assert!(sfr.update(&sc, 7, 12) == 1);
         assert!(finger_by_target_id(&sfr.sorted_fingers, 5).unwrap().schain == sc);
     }
 
@@ -507,4 +615,34 @@ mod tests {
         assert!(all_schains.len() > 0);
 
     }
+
+    #[test]
+    fn test_node_fingers_state_size() {
+        let mut nf = NodeFingers::new(6, &vec![1,3,7,11,54], &vec![5]);
+        let size = nf.state_size();
+        assert!(size.entries == 6);
+        assert!(size.bytes == 6 * size_of::<Finger>());
+
+        nf.enable_next_hops();
+        let sc = SemiChain {
+            final_id: 3,
+            length: 2
+        };
+        assert!(nf.update_with_next_hop(&sc, Some(4), 7));
+        assert!(nf.next_hop(&sc) == Some(4));
+        let size = nf.state_size();
+        assert!(size.entries == 7);
+        assert!(size.bytes == 6 * size_of::<Finger>() + size_of::<SemiChain>() +
+                size_of::<RingKey>());
+
+        // A chain that does not change any finger is not recorded:
+        let sc = SemiChain {
+            final_id: 3,
+            length: 5
+        };
+        assert!(!nf.update_with_next_hop(&sc, Some(4), 7));
+        assert!(nf.next_hop(&sc).is_none());
+        nf.retain_next_hops(&HashSet::new());
+        assert!(nf.state_size().entries == 6);
+    }
 }
@@ -0,0 +1,264 @@
+use std::collections::{HashSet};
+
+use network::{Network};
+use chord::{RingKey};
+use chord::node_fingers::{NodeFingers, SemiChain};
+
+/// A chord route, expanded into the sequence of physical nodes it goes through.
+pub struct ExpandedRoute {
+    /// Indices of all nodes along the route, including the source and the destination.
+    pub nodes: Vec<usize>,
+    /// Sum of edge weights along the route.
+    pub weight: u64,
+    /// Amount of times the route arrives at a node it has already visited.
+    pub revisits: usize,
+}
+
+impl ExpandedRoute {
+    /// Amount of physical hops along the route.
+    pub fn num_hops(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// Check if the route does not visit any node twice.
+    pub fn is_loop_free(&self) -> bool {
+        self.revisits == 0
+    }
+}
+
+/// Follow the recorded next hops of a chain, starting from the node src_index.
+/// Returns the indices of all nodes along the chain, including src_index and the final node,
+/// or None if some next hop was not recorded or is not a neighbor.
+pub fn schain_nodes(src_index: usize, schain: &SemiChain, net: &Network<RingKey>,
+                    fingers: &Vec<NodeFingers>) -> Option<Vec<usize>> {
+
+    let mut nodes = vec![src_index];
+    let mut cur_index = src_index;
+    for length in (1 .. schain.length + 1).rev() {
+        let cur_schain = SemiChain {final_id: schain.final_id, length};
+        let next_index = match fingers[cur_index].next_hop(&cur_schain)
+            .and_then(|next_id| net.node_to_index(&next_id)) {
+            Some(next_index) => next_index,
+            None => return None,
+        };
+        if !net.igraph.contains_edge(cur_index, next_index) {
+            return None;
+        }
+        nodes.push(next_index);
+        cur_index = next_index;
+    }
+
+    if net.index_to_node(cur_index) != Some(&schain.final_id) {
+        return None;
+    }
+    Some(nodes)
+}
+
+/// Record next hops for chains that were learned over the connecting chain nodes:
+/// nodes[0] has learned the chains in new_schains, which continue from the last node
+/// of nodes. Every node in the middle gets the next hop of the remaining part of each chain.
+pub fn install_next_hops(nodes: &[usize], new_schains: &[SemiChain], net: &Network<RingKey>,
+                         fingers: &mut Vec<NodeFingers>) {
+
+    for schain in new_schains {
+        for i in 1 .. nodes.len() - 1 {
+            let rest_schain = SemiChain {
+                final_id: schain.final_id,
+                length: schain.length - i,
+            };
+            let next_id = net.index_to_node(nodes[i + 1]).unwrap().clone();
+            fingers[nodes[i]].set_next_hop(&rest_schain, next_id);
+        }
+    }
+}
+
+/// Forget all next hops that are not used by the chains of current fingers.
+pub fn prune_next_hops(net: &Network<RingKey>, fingers: &mut Vec<NodeFingers>) {
+    let mut keep: Vec<HashSet<SemiChain>> = vec![HashSet::new(); fingers.len()];
+    for x_i in 0 .. net.igraph.node_count() {
+        for schain in fingers[x_i].all_schains() {
+            let nodes = match schain_nodes(x_i, &schain, net, fingers) {
+                Some(nodes) => nodes,
+                None => continue,
+            };
+            for (i, &node_index) in nodes[.. nodes.len() - 1].iter().enumerate() {
+                keep[node_index].insert(SemiChain {
+                    final_id: schain.final_id,
+                    length: schain.length - i,
+                });
+            }
+        }
+    }
+
+    for (nf, keep) in fingers.iter_mut().zip(keep) {
+        nf.retain_next_hops(&keep);
+    }
+}
+
+/// Expand a route (As returned from find_route) into a sequence of physical nodes, by
+/// following next hops hop by hop.
+/// Requires fingers that were converged with next hops recording enabled.
+/// Returns None if the next hop of some chain was not recorded, or if the recorded next hops
+/// do not match the network.
+pub fn expand_route(src_id: RingKey, route: &Vec<SemiChain>, net: &Network<RingKey>,
+                    fingers: &Vec<NodeFingers>) -> Option<ExpandedRoute> {
+
+    let src_index = match net.node_to_index(&src_id) {
+        Some(src_index) => src_index,
+        None => return None,
+    };
+
+    let mut expanded = ExpandedRoute {
+        nodes: vec![src_index],
+        weight: 0,
+        revisits: 0,
+    };
+    let mut visited: HashSet<usize> = HashSet::new();
+    visited.insert(src_index);
+
+    let mut cur_index = src_index;
+    for schain in route {
+        let nodes = match schain_nodes(cur_index, schain, net, fingers) {
+            Some(nodes) => nodes,
+            None => return None,
+        };
+        for &next_index in &nodes[1 ..] {
+            expanded.weight += *net.igraph.edge_weight(cur_index, next_index).unwrap();
+            if !visited.insert(next_index) {
+                expanded.revisits += 1;
+            }
+            expanded.nodes.push(next_index);
+            cur_index = next_index;
+        }
+    }
+
+    Some(expanded)
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{random_weighted_net_chord, gen_network};
+    use state_size::{StateSize};
+    use chord::{init_fingers, converge_fingers, create_semi_chains,
+        find_route, find_path, verify_global_optimality};
+
+    #[test]
+    fn test_expand_route() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let num_nodes = 20;
+        let l: usize = 10;
+        let net = random_weighted_net_chord(num_nodes,3,1,10,l,&mut rng);
+        let mut fingers = init_fingers(&net,l, &mut rng);
+        for nf in fingers.iter_mut() {
+            nf.enable_next_hops();
+        }
+        converge_fingers(&net, &mut fingers,l);
+        assert!(verify_global_optimality(&net, &fingers));
+        let semi_chains = create_semi_chains(&net, &fingers);
+
+        for index_a in 0 .. num_nodes {
+            for index_b in 0 .. num_nodes {
+                let src_id = net.index_to_node(index_a).unwrap().clone();
+                let dst_id = net.index_to_node(index_b).unwrap().clone();
+                let route = find_route(src_id, dst_id, &net, &semi_chains).unwrap();
+                let length = find_path(src_id, dst_id, &net, &semi_chains).unwrap();
+                let expanded = expand_route(src_id, &route, &net, &fingers).unwrap();
+
+                assert!(expanded.num_hops() == length);
+                assert!(expanded.nodes[0] == index_a);
+                assert!(*expanded.nodes.last().unwrap() == index_b);
+                // Weighted route can not be shorter than the shortest path:
+                assert!(expanded.weight >= net.dist(index_a, index_b).unwrap());
+                assert!(expanded.weight >= expanded.num_hops() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_hops_pruned() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        let l = 2 * g + 1;
+        let net = gen_network(0, g, l, 1, 2, &mut rng);
+        let mut fingers = init_fingers(&net,l, &mut rng);
+        for nf in fingers.iter_mut() {
+            nf.enable_next_hops();
+        }
+        converge_fingers(&net, &mut fingers,l);
+
+        // Every current chain can be followed hop by hop, and one next hop is kept for
+        // every node along it, at most:
+        let mut max_entries = 0;
+        for x_i in 0 .. net.igraph.node_count() {
+            for schain in fingers[x_i].all_schains() {
+                let nodes = schain_nodes(x_i, &schain, &net, &fingers).unwrap();
+                assert!(nodes.len() == schain.length + 1);
+                max_entries += schain.length;
+            }
+        }
+        let num_entries: usize = fingers.iter()
+            .map(|nf| nf.state_size().entries)
+            .sum();
+        let num_fingers_entries: usize = fingers.iter_mut()
+            .map(|nf| {
+                nf.retain_next_hops(&HashSet::new());
+                nf.state_size().entries
+            })
+            .sum();
+        assert!(num_entries > num_fingers_entries);
+        assert!(num_entries - num_fingers_entries <= max_entries);
+    }
+
+    #[test]
+    fn test_expand_route_without_next_hops() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 5;
+        let l = 2 * g + 1;
+        let net = gen_network(1, g, l, 1, 2, &mut rng);
+        let mut fingers = init_fingers(&net,l, &mut rng);
+        converge_fingers(&net, &mut fingers,l);
+        let semi_chains = create_semi_chains(&net, &fingers);
+
+        let src_id = net.index_to_node(0).unwrap().clone();
+        let dst_id = net.index_to_node(7).unwrap().clone();
+        let route = find_route(src_id, dst_id, &net, &semi_chains).unwrap();
+        assert!(expand_route(src_id, &route, &net, &fingers).is_none());
+    }
+
+    #[test]
+    fn test_expand_route_loops() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        let l = 2 * g + 1;
+        // A tree network: Concatenated chains often walk back and forth.
+        let net = gen_network(4, g, l, 1, 2, &mut rng);
+        let mut fingers = init_fingers(&net,l, &mut rng);
+        for nf in fingers.iter_mut() {
+            nf.enable_next_hops();
+        }
+        converge_fingers(&net, &mut fingers,l);
+        let semi_chains = create_semi_chains(&net, &fingers);
+
+        let mut num_loops = 0;
+        for index_a in 0 .. net.igraph.node_count() {
+            let src_id = net.index_to_node(index_a).unwrap().clone();
+            let dst_id = net.index_to_node(0).unwrap().clone();
+            let route = find_route(src_id, dst_id, &net, &semi_chains).unwrap();
+            let expanded = expand_route(src_id, &route, &net, &fingers).unwrap();
+            if !expanded.is_loop_free() {
+                num_loops += 1;
+                // A route with loops is longer than the shortest path in a tree:
+                assert!(expanded.weight > net.dist(index_a, 0).unwrap());
+            }
+        }
+        assert!(num_loops > 0);
+    }
+}
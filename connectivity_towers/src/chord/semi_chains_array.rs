@@ -1,6 +1,8 @@
 
 use std::collections::{HashSet};
+use std::mem::size_of;
 use chord::{RingKey, SemiChain};
+use state_size::{StateSize, NodeStateSize};
 
 /// A chains array. Used for quick searching.
 pub struct SemiChainsArray {
@@ -88,6 +90,16 @@ impl SemiChainsArray {
 
 }
 
+impl StateSize for SemiChainsArray {
+    /// Every chain is kept twice: Once in the array and once in the set.
+    fn state_size(&self) -> NodeStateSize {
+        NodeStateSize {
+            entries: self.schains.len(),
+            bytes: (self.schains.len() + self.schains_set.len()) * size_of::<SemiChain>(),
+        }
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -127,4 +139,17 @@ mod tests {
         assert!(*semi_chains_array.find_closest_right(5) == SemiChain{final_id: 5, length: 4});
         assert!(*semi_chains_array.find_closest_left(5) == SemiChain{final_id: 5, length: 4});
     }
+
+    #[test]
+    fn test_semi_chains_array_state_size() {
+        let mut semi_chains_array = SemiChainsArray::new();
+        semi_chains_array.insert_schain(SemiChain{final_id: 3, length: 4});
+        semi_chains_array.insert_schain(SemiChain{final_id: 3, length: 4});
+        semi_chains_array.insert_schain(SemiChain{final_id: 5, length: 5});
+        semi_chains_array.index();
+
+        let size = semi_chains_array.state_size();
+        assert!(size.entries == 2);
+        assert!(size.bytes == 4 * size_of::<SemiChain>());
+    }
 }
@@ -0,0 +1,119 @@
+use std::io::Write;
+use std::time::Duration;
+
+/// Information about one iteration of an iterative (converging) algorithm.
+#[derive(Clone, Debug)]
+pub struct IterStats {
+    /// Iteration number, starting from 0.
+    pub iter: usize,
+    /// Amount of nodes that had at least one entry changed during the iteration.
+    pub nodes_changed: usize,
+    /// Total amount of entries (fingers, coordinate entries, towers info) changed.
+    pub entries_changed: usize,
+    /// Amount of updates that were skipped thanks to version caching.
+    pub skipped_updates: usize,
+    /// Time spent on this iteration.
+    pub elapsed: Duration,
+}
+
+/// An observer for the progress of converging algorithms.
+/// on_iter is called once after every iteration.
+pub trait ConvergenceObserver {
+    fn on_iter(&mut self, stats: &IterStats);
+}
+
+/// An observer that ignores everything.
+pub struct NullObserver;
+
+impl ConvergenceObserver for NullObserver {
+    fn on_iter(&mut self, _stats: &IterStats) {}
+}
+
+/// Keeps the full trace of iterations in memory.
+pub struct TraceRecorder {
+    pub trace: Vec<IterStats>,
+}
+
+impl TraceRecorder {
+    pub fn new() -> TraceRecorder {
+        TraceRecorder {
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl ConvergenceObserver for TraceRecorder {
+    fn on_iter(&mut self, stats: &IterStats) {
+        self.trace.push(stats.clone());
+    }
+}
+
+/// Writes one line for every iteration into a results sink (stdout, a results file etc).
+/// Every line begins with label, so that a few traces can be written to the same sink.
+pub struct WriteRecorder<W: Write> {
+    label: String,
+    writer: W,
+}
+
+impl<W: Write> WriteRecorder<W> {
+    pub fn new(label: &str, writer: W) -> WriteRecorder<W> {
+        WriteRecorder {
+            label: label.to_string(),
+            writer,
+        }
+    }
+}
+
+/// Convert a duration to microseconds.
+fn duration_micros(duration: &Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + (duration.subsec_nanos() / 1000) as u64
+}
+
+impl<W: Write> ConvergenceObserver for WriteRecorder<W> {
+    fn on_iter(&mut self, stats: &IterStats) {
+        writeln!(self.writer, "{}; iter={:4}; nodes_changed={:8}; entries_changed={:10}; \
+                 skipped_updates={:10}; elapsed_us={:10}",
+                 self.label, stats.iter, stats.nodes_changed, stats.entries_changed,
+                 stats.skipped_updates, duration_micros(&stats.elapsed)).unwrap();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_stats(iter: usize) -> IterStats {
+        IterStats {
+            iter,
+            nodes_changed: 3,
+            entries_changed: 7,
+            skipped_updates: 2,
+            elapsed: Duration::new(1, 5000),
+        }
+    }
+
+    #[test]
+    fn test_trace_recorder() {
+        let mut recorder = TraceRecorder::new();
+        recorder.on_iter(&make_stats(0));
+        recorder.on_iter(&make_stats(1));
+        assert!(recorder.trace.len() == 2);
+        assert!(recorder.trace[1].iter == 1);
+    }
+
+    #[test]
+    fn test_write_recorder() {
+        let mut output: Vec<u8> = Vec::new();
+        {
+            let mut recorder = WriteRecorder::new("chord", &mut output);
+            recorder.on_iter(&make_stats(0));
+            recorder.on_iter(&make_stats(1));
+        }
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines.len() == 2);
+        assert!(lines[0].starts_with("chord; iter=   0;"));
+        assert!(lines[1].contains("elapsed_us=   1000005"));
+    }
+}
@@ -0,0 +1,123 @@
+extern crate ring;
+
+use std::hash::{Hash, Hasher};
+
+use self::ring::digest;
+
+/// A Hasher based on sha256.
+/// Unlike DefaultHasher, the output is specified: Integers are fed into sha256 as
+/// big endian bytes (usize and isize as 8 bytes), so every platform and toolchain
+/// calculates the same hash for the same value.
+pub struct Sha256Hasher {
+    ctx: digest::Context,
+}
+
+impl Sha256Hasher {
+    pub fn new() -> Sha256Hasher {
+        Sha256Hasher {
+            ctx: digest::Context::new(&digest::SHA256),
+        }
+    }
+}
+
+impl Default for Sha256Hasher {
+    fn default() -> Self {
+        Sha256Hasher::new()
+    }
+}
+
+impl Hasher for Sha256Hasher {
+    /// The first 8 bytes of the sha256 digest, as a big endian number.
+    fn finish(&self) -> u64 {
+        let digest = self.ctx.clone().finish();
+        digest.as_ref()[.. 8].iter()
+            .fold(0, |res, &byte| (res << 8) | (byte as u64))
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.ctx.update(bytes);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_be_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_be_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_be_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Hash a value together with a salt value, using sha256.
+pub fn sha256_hash<T: Hash>(salt: u64, value: &T) -> u64 {
+    let mut hasher = Sha256Hasher::new();
+    salt.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hash_pinned() {
+        // sha256 of the 16 zero bytes:
+        assert_eq!(sha256_hash(0, &0_u64), 0x374708fff7719dd5);
+        // usize is hashed exactly like u64:
+        assert_eq!(sha256_hash(3, &5_usize), sha256_hash(3, &5_u64));
+        assert_ne!(sha256_hash(3, &5_u64), sha256_hash(4, &5_u64));
+        assert_ne!(sha256_hash(3, &5_u64), sha256_hash(3, &6_u64));
+    }
+
+    #[test]
+    fn test_sha256_hasher_finish_is_repeatable() {
+        let mut hasher = Sha256Hasher::new();
+        hasher.write_u64(7);
+        let first = hasher.finish();
+        assert_eq!(first, hasher.finish());
+        // Writing more data changes the hash:
+        hasher.write_u64(8);
+        assert_ne!(first, hasher.finish());
+    }
+}
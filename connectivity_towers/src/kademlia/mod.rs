@@ -0,0 +1,248 @@
+extern crate petgraph;
+extern crate rand;
+
+pub mod node_buckets;
+
+use std::collections::{HashSet};
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+
+use network::{Network};
+use chord::{RingKey};
+use chord::node_fingers::{SemiChain};
+use self::node_buckets::{NodeBuckets};
+use index_pair::{index_pair, Pair};
+
+/*
+ * A Kademlia like virtual DHT, built over the same mesh as the chord virtual DHT.
+ * Every node maintains k-buckets under the XOR metric, and the buckets are filled
+ * using the same semi chains propagation idea as in chord.
+ */
+
+
+/// Calculate XOR distance between x and y.
+pub fn xor_dist(xk: RingKey, yk: RingKey) -> RingKey {
+    xk ^ yk
+}
+
+/// Get the index of the k-bucket of node x that should contain y.
+/// Returns None if x == y.
+pub fn bucket_index(xk: RingKey, yk: RingKey) -> Option<usize> {
+    match xor_dist(xk, yk) {
+        0 => None,
+        d => Some(63 - (d.leading_zeros() as usize)),
+    }
+}
+
+/// Get a chain of ids between src_id and dst_id.
+/// Every two adjacent ids differ in exactly one bit.
+/// Bits are flipped from the most significant differing bit downwards.
+fn xor_ids_chain(src_id: RingKey, dst_id: RingKey) -> Vec<RingKey> {
+    let mut chain = vec![src_id];
+    let mut cur_id = src_id;
+    while let Some(i) = bucket_index(cur_id, dst_id) {
+        cur_id ^= 1 << i;
+        chain.push(cur_id);
+    }
+    chain
+}
+
+
+/// Generate a vector of maintained xor target_ids for node with id x_id.
+fn gen_target_ids<R: Rng>(x_id: RingKey, net: &Network<RingKey>,
+                                l: usize, mut rng: &mut R) -> Vec<RingKey> {
+
+    let mut target_ids_set: HashSet<RingKey> = HashSet::new();
+
+    // Neighbor connectors:
+    let x_i = net.node_to_index(&x_id).unwrap();
+    let mut neighbors = net.igraph.neighbors(x_i).into_iter().collect::<Vec<_>>();
+    neighbors.sort();
+    for neighbor_index in neighbors {
+        let neighbor_id: RingKey = net.index_to_node(neighbor_index).unwrap().clone();
+        for cur_id in xor_ids_chain(x_id, neighbor_id) {
+            target_ids_set.insert(cur_id);
+        }
+    }
+
+    // Update random fingers:
+    for _ in 0 .. l {
+        // Randomize a finger value in [0, 2^l). Completely random in the key space.
+        let rand_range: Range<RingKey> = Range::new(0u64,2_u64.pow(l as u32));
+        let rand_id = rand_range.ind_sample(&mut rng);
+        target_ids_set.insert(rand_id);
+    }
+
+    target_ids_set.into_iter().collect::<Vec<RingKey>>()
+}
+
+pub fn init_buckets<R: Rng>(net: &Network<RingKey>, k: usize,
+                l: usize, mut rng: &mut R) -> Vec<NodeBuckets> {
+
+    let mut res_buckets = Vec::new();
+    for x_i in 0 .. net.igraph.node_count() {
+        let x_id = net.index_to_node(x_i).unwrap().clone();
+        let target_ids = gen_target_ids(x_id, &net, l, &mut rng);
+        res_buckets.push(NodeBuckets::new(x_id, k, l, &target_ids));
+    }
+
+    res_buckets
+}
+
+
+/// Perform one iteration of buckets for all nodes
+fn iter_buckets(net: &Network<RingKey>,
+                mut buckets: &mut Vec<NodeBuckets>) -> bool {
+
+    // Check if any bucket has changed:
+    let mut has_changed = false;
+
+    for x_i in 0 .. net.igraph.node_count() {
+        for remote_schain in buckets[x_i].all_schains() {
+            let remote_i = net.node_to_index(&remote_schain.final_id).unwrap();
+
+            if x_i == remote_i {
+                continue;
+            }
+
+            // Get two mutable indices (x_i and remote_i):
+            let (m_x_i, m_remote_i) = match index_pair(&mut buckets, x_i, remote_i) {
+                Pair::Two(m_x_i,m_remote_i) => (m_x_i, m_remote_i),
+                _ => panic!("Invalid index pair: {}, {}", x_i, remote_i),
+            };
+
+            // UpdateRequest:
+            has_changed |= m_remote_i.update_by_buckets(&m_x_i,
+                       remote_schain.length);
+
+            // UpdateResponse:
+            has_changed |= m_x_i.update_by_buckets(&m_remote_i,
+                        remote_schain.length);
+        }
+    }
+
+    has_changed
+}
+
+
+/// Get to converging state of buckets for all the network.
+pub fn converge_buckets(net: &Network<RingKey>,
+             mut buckets: &mut Vec<NodeBuckets>) {
+
+    // First iteration: We insert all edges:
+    for x_i in 0 .. net.igraph.node_count() {
+        let mut neighbors = net.igraph.neighbors(x_i).into_iter().collect::<Vec<_>>();
+        neighbors.sort();
+        for neighbor_i in neighbors {
+            let neighbor_id = net.index_to_node(neighbor_i).unwrap().clone();
+            let schain = SemiChain {
+                final_id: neighbor_id,
+                length: 1,
+            };
+            buckets[x_i].update(&schain);
+        }
+    }
+
+    while iter_buckets(&net, &mut buckets) {
+    }
+}
+
+/// Make sure that every bucket contains the k closest keys possible, and every
+/// xor finger reaches the globally best key possible.
+pub fn verify_global_optimality(net: &Network<RingKey>, buckets: &Vec<NodeBuckets>) -> bool {
+    let all_keys: Vec<RingKey> = (0 .. net.igraph.node_count())
+        .map(|x_i| net.index_to_node(x_i).unwrap().clone())
+        .collect::<Vec<_>>();
+
+    for x_i in 0 .. net.igraph.node_count() {
+        if !buckets[x_i].is_optimal(&all_keys) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/// Returns a length of a found path between src_id to dst_id, or
+/// None if no path was found.
+/// Every hop takes the known chain that is XOR closest to dst_id.
+pub fn find_path(src_id: RingKey, dst_id: RingKey, net: &Network<RingKey>,
+                 buckets: &Vec<NodeBuckets>) -> Option<usize> {
+
+    let mut cur_id = src_id;
+    let mut length: usize = 0;
+    while cur_id != dst_id {
+        let cur_buckets = &buckets[net.node_to_index(&cur_id).unwrap()];
+        let schain = match cur_buckets.all_schains().into_iter()
+            .min_by_key(|schain| (xor_dist(schain.final_id, dst_id), schain.length)) {
+            Some(schain) => schain,
+            None => return None,
+        };
+
+        if xor_dist(schain.final_id, dst_id) >= xor_dist(cur_id, dst_id) {
+            return None;
+        }
+
+        length += schain.length;
+        cur_id = schain.final_id;
+    }
+    Some(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{random_net_chord, gen_network};
+
+    #[test]
+    fn test_bucket_index() {
+        assert!(bucket_index(5,5) == None);
+        assert!(bucket_index(4,5) == Some(0));
+        assert!(bucket_index(0,7) == Some(2));
+        assert!(bucket_index(8,7) == Some(3));
+        assert!(bucket_index(0,1 << 63) == Some(63));
+    }
+
+    #[test]
+    fn test_xor_ids_chain() {
+        let chain = xor_ids_chain(0b1010, 0b0111);
+        assert!(chain == vec![0b1010, 0b0010, 0b0110, 0b0111]);
+        assert!(xor_ids_chain(3,3) == vec![3]);
+    }
+
+    #[test]
+    fn test_kademlia_basic() {
+        let seed: &[_] = &[1,2,3,4,9];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let num_nodes = 5;
+        let num_neighbors = 2;
+        let l: usize = 6; // Size of keyspace
+        let net = random_net_chord(num_nodes,num_neighbors,l,&mut rng);
+        let mut buckets = init_buckets(&net, 2, l, &mut rng);
+        converge_buckets(&net, &mut buckets);
+        assert!(verify_global_optimality(&net, &buckets));
+
+        for index_a in 0 .. num_nodes {
+            for index_b in 0 .. num_nodes {
+                let src_id = net.index_to_node(index_a).unwrap().clone();
+                let dst_id = net.index_to_node(index_b).unwrap().clone();
+                let _ = find_path(src_id, dst_id, &net, &buckets).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_kademlia_networks() {
+        for net_type in 0 .. 5 {
+            let seed: &[_] = &[1,2,3,4,net_type];
+            let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+            let g = 6;
+            let l = 2 * g + 1;
+            let net = gen_network(net_type, g, l, 1, 2, &mut rng);
+            let mut buckets = init_buckets(&net, 1, l, &mut rng);
+            converge_buckets(&net, &mut buckets);
+            assert!(verify_global_optimality(&net, &buckets));
+        }
+    }
+}
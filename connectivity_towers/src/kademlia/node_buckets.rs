@@ -0,0 +1,319 @@
+use chord::{RingKey};
+use chord::node_fingers::{SemiChain};
+use kademlia::{xor_dist, bucket_index};
+use state_size::{StateSize, NodeStateSize};
+use std::collections::{HashSet, HashMap};
+use std::mem::size_of;
+
+/// An entry inside a k-bucket.
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct BucketEntry {
+    pub schain: SemiChain,
+    version: usize,
+}
+
+/// Maintained XOR finger: The best known chain to a key as close as possible
+/// (In the XOR metric) to target_id.
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct XorFinger {
+    pub target_id: RingKey,
+    pub schain: SemiChain,
+    version: usize,
+}
+
+pub struct NodeBuckets {
+    id: RingKey,
+    k: usize,
+    /// buckets[i] contains up to k chains to the closest known keys y
+    /// that satisfy 2^i <= x ^ y < 2^(i+1).
+    /// Every bucket is sorted by (xor distance, length).
+    pub buckets: Vec<Vec<BucketEntry>>,
+    pub fingers: Vec<XorFinger>,
+    version: usize, // Current version, used for caching.
+    updated_by: HashMap<RingKey, usize>,
+}
+
+/// Check if proposed new chain is better for the xor finger.
+fn is_xor_finger_better(finger: &XorFinger, schain: &SemiChain) -> bool {
+    let cur_dist = (xor_dist(finger.target_id, finger.schain.final_id), finger.schain.length);
+    let new_dist = (xor_dist(finger.target_id, schain.final_id), schain.length);
+    new_dist < cur_dist
+}
+
+/// Add a chain to a sorted k-bucket of a node with id x_id.
+/// Returns true if the bucket has changed.
+fn update_bucket(bucket: &mut Vec<BucketEntry>, x_id: RingKey, k: usize,
+                 schain: &SemiChain, version: usize) -> bool {
+
+    let bucket_key = |schain: &SemiChain| (xor_dist(x_id, schain.final_id), schain.length);
+
+    // Every final_id may appear only once inside a bucket:
+    match bucket.iter().position(|entry| entry.schain.final_id == schain.final_id) {
+        Some(index) => {
+            if bucket[index].schain.length <= schain.length {
+                return false;
+            }
+            // Xor distance is the same, so the order of the bucket is kept.
+            bucket[index].schain = schain.clone();
+            bucket[index].version = version;
+            return true;
+        },
+        None => {},
+    };
+
+    let new_key = bucket_key(schain);
+    let insert_index = match bucket.binary_search_by_key(&new_key,
+                                                         |entry| bucket_key(&entry.schain)) {
+        Ok(index) => index,
+        Err(index) => index,
+    };
+
+    if insert_index >= k {
+        return false;
+    }
+
+    bucket.insert(insert_index, BucketEntry {
+        schain: schain.clone(),
+        version,
+    });
+    bucket.truncate(k);
+    true
+}
+
+impl NodeBuckets {
+    pub fn new(x_id: RingKey, k: usize, l: usize, target_ids: &Vec<RingKey>) -> NodeBuckets {
+        let mut nb = NodeBuckets {
+            id: x_id,
+            k,
+            buckets: vec![Vec::new(); l],
+            fingers: Vec::new(),
+            version: 0,
+            updated_by: HashMap::new(),
+        };
+
+        // Insert all xor fingers:
+        for &target_id in target_ids {
+            nb.fingers.push(
+                XorFinger {
+                    target_id,
+                    schain: SemiChain {
+                        final_id: x_id,
+                        length: 0,
+                    },
+                    version: 1,
+                },
+            );
+        }
+        nb.fingers.sort_by_key(|finger| finger.target_id);
+        nb.fingers.dedup_by_key(|finger| finger.target_id);
+
+        nb
+    }
+
+    /// Add a new known chain, possibly updating existing buckets and fingers.
+    /// Returns true if anything was updated.
+    pub fn update(&mut self, schain: &SemiChain) -> bool {
+        let mut has_changed: bool = false;
+        self.version += 1;
+        let version = self.version;
+
+        if schain.final_id != self.id {
+            let i = bucket_index(self.id, schain.final_id).unwrap();
+            has_changed |= update_bucket(&mut self.buckets[i], self.id, self.k,
+                                         &schain, version);
+        }
+
+        for finger in &mut self.fingers {
+            if is_xor_finger_better(finger, &schain) {
+                finger.schain = schain.clone();
+                finger.version = version;
+                has_changed = true;
+            }
+        }
+
+        // Version is increased only if anything has changed:
+        if !has_changed {
+            self.version -= 1;
+        }
+
+        has_changed
+    }
+
+    /// Get all chains this node maintains, with their versions.
+    fn all_versioned_schains(&self) -> Vec<(SemiChain, usize)> {
+        let mut unique_schains: HashSet<(SemiChain, usize)> = HashSet::new();
+        for bucket in &self.buckets {
+            for entry in bucket {
+                unique_schains.insert((entry.schain.clone(), entry.version));
+            }
+        }
+        for finger in &self.fingers {
+            unique_schains.insert((finger.schain.clone(), finger.version));
+        }
+
+        let mut unique_schains_vec = unique_schains.into_iter().collect::<Vec<_>>();
+        unique_schains_vec.sort_by_key(|&(ref schain, version)|
+                                       (schain.final_id, schain.length, version));
+        unique_schains_vec
+    }
+
+    /// Get all chains this node maintains (Both in buckets and in fingers).
+    pub fn all_schains(&self) -> Vec<SemiChain> {
+        let mut unique_schains_vec = self.all_versioned_schains().into_iter()
+            .map(|(schain, _)| schain)
+            .collect::<Vec<SemiChain>>();
+        unique_schains_vec.dedup();
+        unique_schains_vec
+    }
+
+    /// Update buckets by all chains in buckets_src,
+    /// assuming that there is a connecting chain between the two
+    /// of length chain_length.
+    /// Return if anything in self has changed.
+    pub fn update_by_buckets(&mut self, buckets_src: &NodeBuckets,
+                 chain_length: usize) -> bool {
+
+        // Get last_version we have of buckets_src.
+        // 0 is a reserved version number, which means we know nothing of buckets_src.
+        let last_version = match self.updated_by.get(&buckets_src.id) {
+            Some(&last_version) => last_version,
+            None => 0,
+        };
+
+        assert!(last_version <= buckets_src.version);
+
+        if last_version == buckets_src.version {
+            // We are already updated about this version of buckets_src.
+            return false;
+        }
+
+        let mut has_changed = false;
+
+        for (schain, version) in buckets_src.all_versioned_schains() {
+            if last_version >= version {
+                // We don't consider this chain if its version is too old.
+                continue
+            }
+            let new_schain = SemiChain {
+                final_id: schain.final_id,
+                length: schain.length + chain_length,
+            };
+
+            has_changed |= self.update(&new_schain);
+        }
+
+        // Update known version of buckets_src:
+        self.updated_by.insert(buckets_src.id, buckets_src.version);
+
+        has_changed
+    }
+
+    /// Check if every bucket contains the k globally closest keys possible,
+    /// and every finger reaches the globally closest key to its target_id.
+    pub fn is_optimal(&self, all_keys: &Vec<RingKey>) -> bool {
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            let mut best_keys = all_keys.iter()
+                .filter(|&&key| bucket_index(self.id, key) == Some(i))
+                .map(|&key| key)
+                .collect::<Vec<RingKey>>();
+            best_keys.sort_by_key(|&key| xor_dist(self.id, key));
+            best_keys.truncate(self.k);
+
+            let bucket_keys = bucket.iter()
+                .map(|entry| entry.schain.final_id)
+                .collect::<Vec<RingKey>>();
+
+            if bucket_keys != best_keys {
+                return false;
+            }
+        }
+
+        for finger in &self.fingers {
+            let best_key = all_keys.iter()
+                .min_by_key(|&&key| xor_dist(finger.target_id, key))
+                .unwrap();
+            if finger.schain.final_id != *best_key {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl StateSize for NodeBuckets {
+    /// Bucket entries, xor fingers and known versions of other nodes (updated_by).
+    fn state_size(&self) -> NodeStateSize {
+        let num_bucket_entries: usize = self.buckets.iter()
+            .map(|bucket| bucket.len())
+            .sum();
+        NodeStateSize {
+            entries: num_bucket_entries + self.fingers.len() + self.updated_by.len(),
+            bytes: num_bucket_entries * size_of::<BucketEntry>() +
+                self.fingers.len() * size_of::<XorFinger>() +
+                self.updated_by.len() * (size_of::<RingKey>() + size_of::<usize>()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schain(final_id: RingKey, length: usize) -> SemiChain {
+        SemiChain {
+            final_id,
+            length,
+        }
+    }
+
+    #[test]
+    fn test_update_bucket_basic() {
+        // Node id is 0b1000, so bucket 2 contains keys in [4,8)
+        let mut nb = NodeBuckets::new(8, 2, 5, &vec![]);
+        assert!(nb.update(&schain(14, 3)));
+        assert!(nb.update(&schain(13, 4)));
+        assert!(nb.buckets[2].len() == 2);
+        assert!(nb.buckets[2][0].schain == schain(13,4));
+        assert!(nb.buckets[2][1].schain == schain(14,3));
+
+        // Further than both entries, bucket is full:
+        assert!(!nb.update(&schain(15, 1)));
+        // Closer than both entries:
+        assert!(nb.update(&schain(12, 1)));
+        assert!(nb.buckets[2].iter().map(|e| e.schain.final_id).collect::<Vec<_>>() == vec![12,13]);
+    }
+
+    #[test]
+    fn test_update_bucket_shorter_chain() {
+        let mut nb = NodeBuckets::new(8, 2, 5, &vec![]);
+        assert!(nb.update(&schain(9, 3)));
+        assert!(!nb.update(&schain(9, 3)));
+        assert!(!nb.update(&schain(9, 4)));
+        assert!(nb.update(&schain(9, 2)));
+        assert!(nb.buckets[0].len() == 1);
+        assert!(nb.buckets[0][0].schain == schain(9,2));
+    }
+
+    #[test]
+    fn test_update_xor_finger() {
+        let mut nb = NodeBuckets::new(8, 1, 5, &vec![20]);
+        assert!(nb.update(&schain(16, 3)));
+        assert!(nb.fingers[0].schain == schain(16,3));
+        // 21 ^ 20 = 1 < 16 ^ 20 = 4:
+        assert!(nb.update(&schain(21, 5)));
+        assert!(nb.fingers[0].schain == schain(21,5));
+        assert!(!nb.update(&schain(17, 1)));
+    }
+
+    #[test]
+    fn test_is_optimal() {
+        let mut nb = NodeBuckets::new(8, 1, 5, &vec![20]);
+        let all_keys = vec![8, 9, 12, 21];
+        assert!(!nb.is_optimal(&all_keys));
+        nb.update(&schain(9, 1));
+        nb.update(&schain(12, 1));
+        assert!(!nb.is_optimal(&all_keys));
+        nb.update(&schain(21, 1));
+        assert!(nb.is_optimal(&all_keys));
+    }
+}
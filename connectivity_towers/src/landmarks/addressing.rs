@@ -0,0 +1,174 @@
+use std::hash::{Hash};
+
+use network::{Network};
+use hash_util::{sha256_hash};
+use landmarks::coord_mappers::{max_dist};
+
+/// A unique address of a node: Its landmarks coordinate, extended by a suffix
+/// calculated from a hash of the node's id.
+/// Nodes that share the same coordinate are told apart by the suffix.
+#[derive(Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct NodeAddress {
+    pub coord: Vec<u64>,
+    pub suffix: u64,
+}
+
+/// Salt used for hashing node ids into address suffixes.
+const ID_SUFFIX_SALT: u64 = 0;
+
+/// Calculate an address suffix from a node id.
+pub fn id_suffix<Node: Hash>(node: &Node) -> u64 {
+    sha256_hash(ID_SUFFIX_SALT, node)
+}
+
+/// Calculate the addresses of all nodes in the network.
+pub fn build_addresses<Node: Hash + Eq + Clone>(net: &Network<Node>,
+                                             coords: &Vec<Vec<u64>>) -> Vec<NodeAddress> {
+    coords.iter().enumerate()
+        .map(|(node_index, coord)| NodeAddress {
+            coord: coord.clone(),
+            suffix: id_suffix(net.index_to_node(node_index).unwrap()),
+        })
+        .collect()
+}
+
+/// Distance between two addresses: max_dist between the coordinates first,
+/// and then xor distance between the suffixes.
+pub fn address_dist(a: &NodeAddress, b: &NodeAddress) -> (u64, u64) {
+    (max_dist(&a.coord, &b.coord), a.suffix ^ b.suffix)
+}
+
+/// Find the node with the address closest to the target address, by going over the
+/// addresses of all the nodes. This requires global knowledge, and is used as an oracle
+/// for checking route_to_address.
+/// Unlike choosing randomly between all nodes with minimal max_dist to the target coordinate,
+/// the result is deterministic: Every node that knows the addresses will choose the same node.
+pub fn closest_node_by_address_oracle(target: &NodeAddress, addresses: &Vec<NodeAddress>) -> usize {
+    (0 .. addresses.len())
+        .min_by_key(|&i| (address_dist(target, &addresses[i]), i))
+        .unwrap()
+}
+
+/// Route a message from src_index towards the target address, using only the addresses
+/// of neighbors: In every step the message is forwarded to the neighbor with the address
+/// closest to the target, as long as it is closer than the current node.
+/// Returns the path of the message. The last node on the path is a local minimum, which
+/// is not always the node returned by closest_node_by_address_oracle.
+pub fn route_to_address<Node: Hash + Eq + Clone>(src_index: usize, target: &NodeAddress,
+                            net: &Network<Node>, addresses: &Vec<NodeAddress>) -> Vec<usize> {
+    let mut path = vec![src_index];
+    let mut cur_index = src_index;
+    loop {
+        let cur_dist = address_dist(target, &addresses[cur_index]);
+        let best_nei = net.igraph.neighbors(cur_index)
+            .map(|nei_index| (address_dist(target, &addresses[nei_index]), nei_index))
+            .min();
+        match best_nei {
+            Some((nei_dist, nei_index)) if nei_dist < cur_dist => {
+                cur_index = nei_index;
+                path.push(cur_index);
+            },
+            _ => return path,
+        }
+    }
+}
+
+/// Check if all addresses are unique.
+pub fn is_address_unique(addresses: &Vec<NodeAddress>) -> bool {
+    let mut sorted_addresses = addresses.iter().collect::<Vec<_>>();
+    sorted_addresses.sort();
+    sorted_addresses.windows(2).all(|w| w[0] != w[1])
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network};
+    use landmarks::coords::{build_coords, choose_landmarks, is_coord_unique};
+
+    #[test]
+    fn test_addresses_unique() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 8;
+        // A tree with a few landmarks has many colliding coordinates:
+        let net = gen_network(4, g, 2 * g + 1, 1, 2, &mut rng);
+        let landmarks = choose_landmarks(&net, 3, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        assert!(!is_coord_unique(&coords));
+
+        let addresses = build_addresses(&net, &coords);
+        assert!(is_address_unique(&addresses));
+
+        // Every node is the closest node to its own address:
+        for (node_index, address) in addresses.iter().enumerate() {
+            assert!(closest_node_by_address_oracle(address, &addresses) == node_index);
+        }
+    }
+
+    #[test]
+    fn test_route_to_address() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 8;
+        let net = gen_network(0, g, 2 * g + 1, 1, 2, &mut rng);
+        let landmarks = choose_landmarks(&net, g * g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let addresses = build_addresses(&net, &coords);
+
+        let num_nodes = net.igraph.node_count();
+        let mut num_delivered = 0;
+        for dst_index in 0 .. num_nodes {
+            let src_index = (dst_index * 7 + 3) % num_nodes;
+            let path = route_to_address(src_index, &addresses[dst_index], &net, &addresses);
+            // Every step is along an edge, and gets strictly closer to the target:
+            assert!(path[0] == src_index);
+            for w in path.windows(2) {
+                assert!(net.igraph.contains_edge(w[0], w[1]));
+                assert!(address_dist(&addresses[dst_index], &addresses[w[1]]) <
+                        address_dist(&addresses[dst_index], &addresses[w[0]]));
+            }
+            if *path.last().unwrap() == dst_index {
+                num_delivered += 1;
+            }
+        }
+        // Greedy routing may get stuck at a local minimum, but most messages should arrive
+        // (About 80% for this network):
+        assert!(num_delivered * 4 > num_nodes * 3);
+    }
+
+    #[test]
+    fn test_route_to_address_path() {
+        // A path network 0 - 1 - 2 - 3, with one landmark at 0:
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 4 {
+            net.add_node(x);
+        }
+        for x in 0 .. 3 {
+            net.igraph.add_edge(x, x + 1, 1);
+        }
+        let coords = build_coords(&net, &vec![0]).unwrap();
+        let addresses = build_addresses(&net, &coords);
+        assert!(route_to_address(3, &addresses[0], &net, &addresses) == vec![3, 2, 1, 0]);
+        assert!(route_to_address(1, &addresses[1], &net, &addresses) == vec![1]);
+    }
+
+    #[test]
+    fn test_closest_node_by_address_tie() {
+        let addresses = vec![
+            NodeAddress {coord: vec![1, 2], suffix: 0b1100},
+            NodeAddress {coord: vec![1, 2], suffix: 0b0011},
+            NodeAddress {coord: vec![5, 5], suffix: 0b0001},
+        ];
+        // Both first nodes have the same coordinate. The suffix decides:
+        let target = NodeAddress {coord: vec![1, 3], suffix: 0b1000};
+        assert!(closest_node_by_address_oracle(&target, &addresses) == 0);
+        let target = NodeAddress {coord: vec![1, 3], suffix: 0b0001};
+        assert!(closest_node_by_address_oracle(&target, &addresses) == 1);
+        let target = NodeAddress {coord: vec![5, 4], suffix: 0b1100};
+        assert!(closest_node_by_address_oracle(&target, &addresses) == 2);
+    }
+}
@@ -0,0 +1,329 @@
+use std::hash::Hash;
+use std::collections::{HashMap, BinaryHeap};
+use std::cmp::Reverse;
+use std::mem::size_of;
+
+use network::{Network};
+use state_size::{NodeStateSize, StateSize};
+use router::{Router, RouteSummary};
+
+/// Address of a destination node in the compact routing scheme:
+/// The landmark closest to the node, and the port of the landmark leading towards the node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactAddress {
+    pub node: usize,
+    pub landmark: usize,
+    /// A port of a node is the position of a neighbour inside the sorted list of its neighbours.
+    /// None if the node is the landmark itself.
+    pub port: Option<usize>,
+}
+
+/// Routing table of one node.
+pub struct CompactTable {
+    /// Next hop to every node of the cluster of this node: All the nodes w such that
+    /// this node is inside the ball of w. (Closer to w than the closest landmark of w).
+    pub cluster: HashMap<usize, usize>,
+    /// Next hop to every landmark. None if the landmark is not reachable.
+    pub landmarks: HashMap<usize, usize>,
+}
+
+impl StateSize for CompactTable {
+    fn state_size(&self) -> NodeStateSize {
+        let entries = self.cluster.len() + self.landmarks.len();
+        NodeStateSize {
+            entries,
+            bytes: entries * 2 * size_of::<usize>(),
+        }
+    }
+}
+
+/// Routing tables and addresses of all nodes in the network.
+pub struct CompactRouting {
+    pub tables: Vec<CompactTable>,
+    /// Address of every node. None if the node can not reach any landmark.
+    pub addresses: Vec<Option<CompactAddress>>,
+    /// Sorted neighbours of every node. The position of a neighbour in this list is its port.
+    pub ports: Vec<Vec<usize>>,
+}
+
+/// Result of routing a message using the compact routing scheme.
+pub struct CompactRoute {
+    pub hops: usize,
+    /// Sum of weights of all edges the message has traveled through.
+    pub weight: u64,
+}
+
+/// Calculate a shortest paths tree from root, including only nodes with distance
+/// strictly smaller than bound (If a bound is given).
+/// Returns node -> (distance from root, parent). Ties are broken by node index, hence
+/// the tree is deterministic.
+fn shortest_paths_tree<Node: Hash + Eq + Clone>(net: &Network<Node>, root: usize,
+                       bound: Option<u64>) -> HashMap<usize, (u64, Option<usize>)> {
+
+    let mut tree: HashMap<usize, (u64, Option<usize>)> = HashMap::new();
+    let mut pending: HashMap<usize, (u64, Option<usize>)> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    pending.insert(root, (0, None));
+    heap.push(Reverse((0, root)));
+
+    while let Some(Reverse((dist, node))) = heap.pop() {
+        if tree.contains_key(&node) || pending[&node].0 != dist {
+            continue;
+        }
+        tree.insert(node, pending[&node]);
+
+        for (_, nei, &weight) in net.igraph.edges(node) {
+            let nei_dist = dist + weight;
+            if tree.contains_key(&nei) || bound.map_or(false, |b| nei_dist >= b) {
+                continue;
+            }
+            let is_better = match pending.get(&nei) {
+                None => true,
+                Some(&(old_dist, old_parent)) =>
+                    (nei_dist, Some(node)) < (old_dist, old_parent),
+            };
+            if is_better {
+                pending.insert(nei, (nei_dist, Some(node)));
+                heap.push(Reverse((nei_dist, nei)));
+            }
+        }
+    }
+    tree
+}
+
+/// Get the neighbours of a node, sorted. The position of a neighbour in this list is its port.
+fn sorted_neighbors<Node: Hash + Eq + Clone>(net: &Network<Node>, node: usize) -> Vec<usize> {
+    let mut neighbors = net.igraph.neighbors(node).collect::<Vec<usize>>();
+    neighbors.sort();
+    neighbors
+}
+
+/// Build the routing tables and addresses of a Thorup-Zwick style compact routing scheme.
+///
+/// Every node keeps a route to every landmark, and to every node of its cluster.
+/// A node v is in the cluster of u if u is closer to v than the closest landmark of v.
+/// The address of a node is its closest landmark, together with the port of the landmark
+/// along the shortest path to the node. All the next nodes along this path have the node
+/// in their cluster.
+pub fn build_compact_routing<Node: Hash + Eq + Clone>(net: &Network<Node>,
+                     landmarks: &Vec<usize>) -> CompactRouting {
+
+    let num_nodes = net.igraph.node_count();
+    let mut tables = (0 .. num_nodes)
+        .map(|_| CompactTable {
+            cluster: HashMap::new(),
+            landmarks: HashMap::new(),
+        })
+        .collect::<Vec<_>>();
+
+    // Closest landmark for every node: (distance, landmark)
+    let mut closest_landmark: Vec<Option<(u64, usize)>> = vec![None; num_nodes];
+    let mut landmark_trees: HashMap<usize, HashMap<usize, (u64, Option<usize>)>> = HashMap::new();
+
+    for &landmark in landmarks {
+        let tree = shortest_paths_tree(net, landmark, None);
+        for (&node, &(dist, parent)) in &tree {
+            if let Some(parent) = parent {
+                tables[node].landmarks.insert(landmark, parent);
+            }
+            if closest_landmark[node].map_or(true, |cur| (dist, landmark) < cur) {
+                closest_landmark[node] = Some((dist, landmark));
+            }
+        }
+        landmark_trees.insert(landmark, tree);
+    }
+
+    // Clusters: Every node w is added to the tables of all the nodes inside its ball.
+    for w in 0 .. num_nodes {
+        let bound = closest_landmark[w].map(|(dist, _)| dist);
+        for (&node, &(_, parent)) in &shortest_paths_tree(net, w, bound) {
+            if let Some(parent) = parent {
+                tables[node].cluster.insert(w, parent);
+            }
+        }
+    }
+
+    let ports = (0 .. num_nodes)
+        .map(|node| sorted_neighbors(net, node))
+        .collect::<Vec<Vec<usize>>>();
+
+    // Addresses: The port of the first hop from the closest landmark down to the node.
+    let addresses = (0 .. num_nodes)
+        .map(|node| closest_landmark[node].map(|(_, landmark)| {
+            let tree = &landmark_trees[&landmark];
+            let mut cur_node = node;
+            let mut port = None;
+            while let Some(parent) = tree[&cur_node].1 {
+                if parent == landmark {
+                    port = ports[parent].iter().position(|&nei| nei == cur_node);
+                    break;
+                }
+                cur_node = parent;
+            }
+            CompactAddress {node, landmark, port}
+        }))
+        .collect::<Vec<_>>();
+
+    CompactRouting {tables, addresses, ports}
+}
+
+/// Route a message from src_node to dst_node using the compact routing scheme.
+/// At every node: If dst_node is in the cluster of the current node, the message is forwarded
+/// directly to dst_node. Otherwise it is forwarded to the closest landmark of dst_node, which
+/// sends it through the port from the address. From there dst_node is in the cluster of
+/// every node along the way.
+///
+/// The route is at most 3 times longer than the shortest path.
+/// Returns None if dst_node has no address or could not be reached.
+pub fn find_path_compact<Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize,
+         net: &Network<Node>, routing: &CompactRouting) -> Option<CompactRoute> {
+
+    let address = match routing.addresses[dst_node] {
+        Some(ref address) => address,
+        None => return None,
+    };
+
+    let mut route = CompactRoute {
+        hops: 0,
+        weight: 0,
+    };
+
+    let mut cur_node = src_node;
+    while cur_node != dst_node {
+        let table = &routing.tables[cur_node];
+        let next_node = if let Some(&next_node) = table.cluster.get(&dst_node) {
+            next_node
+        } else if cur_node == address.landmark {
+            *routing.ports[cur_node].get(address.port?)?
+        } else {
+            *table.landmarks.get(&address.landmark)?
+        };
+
+        route.hops += 1;
+        route.weight += *net.igraph.edge_weight(cur_node, next_node)?;
+        cur_node = next_node;
+
+        // Should never happen. Protects against routing loops:
+        if route.hops > 2 * net.igraph.node_count() {
+            return None
+        }
+    }
+    Some(route)
+}
+
+impl<Node: Hash + Eq + Clone> Router<Node> for CompactRouting {
+    fn name(&self) -> &str {
+        "compact"
+    }
+
+    fn route(&self, src_node: usize, dst_node: usize, net: &Network<Node>) -> Option<RouteSummary> {
+        find_path_compact(src_node, dst_node, net, self)
+            .map(|route| RouteSummary {hops: route.hops, weight: route.weight})
+    }
+}
+
+/// Size of the routing table every node keeps.
+pub fn compact_state_sizes(routing: &CompactRouting) -> Vec<NodeStateSize> {
+    routing.tables.iter()
+        .map(|table| table.state_size())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network_kind, NetworkKind};
+    use landmarks::coords::{choose_landmarks};
+
+    #[test]
+    fn test_shortest_paths_tree() {
+        // A square 0-1-2-3-0 with a heavy edge 0-2:
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 4 {
+            net.add_node(x);
+        }
+        net.igraph.add_edge(0, 1, 1);
+        net.igraph.add_edge(1, 2, 1);
+        net.igraph.add_edge(2, 3, 1);
+        net.igraph.add_edge(3, 0, 1);
+        net.igraph.add_edge(0, 2, 5);
+
+        let tree = shortest_paths_tree(&net, 0, None);
+        assert!(tree[&0] == (0, None));
+        assert!(tree[&1] == (1, Some(0)));
+        // Two shortest paths to 2. The parent with the smaller index is chosen:
+        assert!(tree[&2] == (2, Some(1)));
+        assert!(tree[&3] == (1, Some(0)));
+
+        let tree = shortest_paths_tree(&net, 0, Some(2));
+        assert!(tree.len() == 3);
+        assert!(!tree.contains_key(&2));
+    }
+
+    #[test]
+    fn test_compact_routing_stretch() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 7;
+        for kind in NetworkKind::all() {
+            let net = gen_network_kind(kind, g, 2 * g + 1, 1, 5, &mut rng);
+            let landmarks = choose_landmarks(&net, 2 * g, &mut rng);
+            let routing = build_compact_routing(&net, &landmarks);
+
+            let num_nodes = net.igraph.node_count();
+            for src_node in (0 .. num_nodes).step_by(3) {
+                for dst_node in 0 .. num_nodes {
+                    let route = find_path_compact(src_node, dst_node, &net, &routing).unwrap();
+                    assert!(routing.route(src_node, dst_node, &net) ==
+                            Some(RouteSummary {hops: route.hops, weight: route.weight}));
+                    let dist = net.dist(src_node, dst_node).unwrap();
+                    assert!(route.weight >= dist);
+                    assert!(route.weight <= 3 * dist);
+                    // Nodes in the cluster are reached using a shortest path:
+                    if routing.tables[src_node].cluster.contains_key(&dst_node) {
+                        assert!(route.weight == dist);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compact_routing_addresses() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        let net = gen_network_kind(NetworkKind::Rand, g, 2 * g + 1, 1, 2, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let routing = build_compact_routing(&net, &landmarks);
+
+        for node in 0 .. net.igraph.node_count() {
+            assert!(routing.ports[node] == sorted_neighbors(&net, node));
+        }
+        for &landmark in &landmarks {
+            let address = routing.addresses[landmark].clone().unwrap();
+            assert!(address.landmark == landmark);
+            assert!(address.port.is_none());
+        }
+        // The port of the landmark leads to a node that knows the way to the destination:
+        for node in 0 .. net.igraph.node_count() {
+            let address = routing.addresses[node].clone().unwrap();
+            if let Some(port) = address.port {
+                let first_hop = routing.ports[address.landmark][port];
+                assert!(first_hop == node || routing.tables[first_hop].cluster.contains_key(&node));
+            }
+        }
+        // Every node keeps a route to every landmark except itself:
+        let sizes = compact_state_sizes(&routing);
+        for (node, size) in sizes.iter().enumerate() {
+            let expected_landmarks = landmarks.len() -
+                if landmarks.contains(&node) { 1 } else { 0 };
+            assert!(routing.tables[node].landmarks.len() == expected_landmarks);
+            assert!(size.entries == expected_landmarks + routing.tables[node].cluster.len());
+        }
+    }
+}
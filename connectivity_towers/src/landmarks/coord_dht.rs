@@ -0,0 +1,237 @@
+extern crate rand;
+
+use std::hash::Hash;
+use std::collections::{HashSet};
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+
+use network::{Network};
+use state_size::{DistStats, dist_stats};
+use landmarks::{KnownNode, find_path_landmarks_areas_by_coord};
+use landmarks::coord_randomizers::{CoordRandomizer, CoordSpace};
+use landmarks::coord_hash::{hash_key_to_coord};
+
+/// Result of routing a message to the location of a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DhtOp {
+    /// The node where the routing has ended.
+    pub node: usize,
+    /// Length of the path the message has traveled.
+    pub path_len: u64,
+    /// For lookups: Was the key found along the way.
+    pub found: bool,
+}
+
+/// A distributed hash table over landmarks coordinates (Distributed Post Office).
+/// A key is hashed into a random coordinate, and stored at the node reached by
+/// routing towards this coordinate (find_path_landmarks_areas_by_coord).
+///
+/// Keys are byte strings, hashed with sha256 (See hash_key_to_coord). The coordinate of a key
+/// does not depend on the platform, the toolchain or on the process that calculates it.
+pub struct CoordDht<'a, Node: 'a> {
+    net: &'a Network<Node>,
+    space: CoordSpace<'a>,
+    areas: &'a Vec<Vec<KnownNode>>,
+    /// Method for turning keys into coordinates.
+    randomizer: &'a dyn CoordRandomizer,
+    /// Maximum amount of visits to a valley before routing to a coordinate stops.
+    max_visits: usize,
+    /// Salt for hashing keys into coordinates.
+    salt: u64,
+    /// Keys stored at every node.
+    storage: Vec<HashSet<Vec<u8>>>,
+}
+
+impl<'a, Node: Hash + Eq + Clone> CoordDht<'a, Node> {
+    pub fn new(net: &'a Network<Node>, coords: &'a Vec<Vec<u64>>, landmarks: &'a Vec<usize>,
+               areas: &'a Vec<Vec<KnownNode>>, randomizer: &'a dyn CoordRandomizer,
+               max_visits: usize, salt: u64) -> Self {
+        CoordDht {
+            net,
+            space: CoordSpace::new(landmarks, coords),
+            areas,
+            randomizer,
+            max_visits,
+            salt,
+            storage: vec![HashSet::new(); net.igraph.node_count()],
+        }
+    }
+
+    /// Deterministically hash a key into a coordinate.
+    /// Every node that knows the landmarks coordinates gets the same coordinate for the key.
+    pub fn key_coord(&self, key: &[u8]) -> Vec<u64> {
+        hash_key_to_coord(self.randomizer, &self.space, key, self.salt)
+    }
+
+    /// Route from src_node towards the coordinate of a key.
+    /// Returns (final node, path length, valleys visited along the way).
+    fn route_to_key<R: Rng>(&self, key: &[u8], src_node: usize, rng: &mut R)
+        -> (usize, u64, HashSet<usize>) {
+
+        let key_coord = self.key_coord(key);
+        find_path_landmarks_areas_by_coord(src_node, &key_coord, self.max_visits,
+                        self.net, self.space.coords, self.space.landmarks, self.areas, rng)
+    }
+
+    /// Store a key, starting from src_node.
+    pub fn store<R: Rng>(&mut self, key: &[u8], src_node: usize, rng: &mut R) -> DhtOp {
+        let (node, path_len, _) = self.route_to_key(key, src_node, rng);
+        self.storage[node].insert(key.to_vec());
+        DhtOp {node, path_len, found: true}
+    }
+
+    /// Look for a key, starting from src_node.
+    /// The key is found if it is stored at one of the valleys visited by the message, or at
+    /// the node where the routing has ended.
+    pub fn lookup<R: Rng>(&self, key: &[u8], src_node: usize, rng: &mut R) -> DhtOp {
+        let (final_node, path_len, valleys) = self.route_to_key(key, src_node, rng);
+
+        let mut candidates = valleys.into_iter().collect::<Vec<usize>>();
+        // Sort for determinism:
+        candidates.sort();
+        candidates.insert(0, final_node);
+
+        match candidates.into_iter().find(|&node| self.storage[node].contains(key)) {
+            Some(node) => DhtOp {node, path_len, found: true},
+            None => DhtOp {node: final_node, path_len, found: false},
+        }
+    }
+
+    /// Amount of keys stored at every node.
+    pub fn load(&self) -> Vec<usize> {
+        self.storage.iter().map(|keys| keys.len()).collect()
+    }
+}
+
+/// Results of storing and looking up keys in a CoordDht.
+#[derive(Debug)]
+pub struct CoordDhtEval {
+    pub lookup_success_rate: f64,
+    pub avg_store_path_len: f64,
+    pub avg_lookup_path_len: f64,
+    /// Distribution of the amount of keys stored at every node.
+    pub load: DistStats,
+    /// Maximum load divided by the average load. 1 means perfect balance.
+    pub max_load_ratio: f64,
+}
+
+/// Store num_keys keys from random nodes, and then look up every key from num_lookups
+/// random nodes.
+pub fn eval_coord_dht<R: Rng, Node: Hash + Eq + Clone>(dht: &mut CoordDht<Node>,
+             num_keys: usize, num_lookups: usize, rng: &mut R) -> CoordDhtEval {
+
+    let node_range: Range<usize> = Range::new(0, dht.net.igraph.node_count());
+
+    let mut sum_store_len: u64 = 0;
+    for key in 0 .. num_keys {
+        let src_node = node_range.ind_sample(rng);
+        sum_store_len += dht.store(key.to_string().as_bytes(), src_node, rng).path_len;
+    }
+
+    let mut num_found: usize = 0;
+    let mut sum_lookup_len: u64 = 0;
+    for key in 0 .. num_keys {
+        for _ in 0 .. num_lookups {
+            let src_node = node_range.ind_sample(rng);
+            let op = dht.lookup(key.to_string().as_bytes(), src_node, rng);
+            if op.found {
+                num_found += 1;
+            }
+            sum_lookup_len += op.path_len;
+        }
+    }
+
+    let load = dist_stats(&dht.load());
+    let num_ops = (num_keys * num_lookups) as f64;
+    CoordDhtEval {
+        lookup_success_rate: (num_found as f64) / num_ops,
+        avg_store_path_len: (sum_store_len as f64) / (num_keys as f64),
+        avg_lookup_path_len: (sum_lookup_len as f64) / num_ops,
+        max_load_ratio: (load.max as f64) / load.mean,
+        load,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network};
+    use landmarks::coord_randomizers::{RwDirectionalRandomizer};
+    use landmarks::{gen_areas};
+    use landmarks::coords::{build_coords, choose_landmarks};
+
+    #[test]
+    fn test_coord_dht_store_lookup() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 7;
+        // Large weights are required for random walk coordinates:
+        let net = gen_network(0, g, 2 * g + 1, 0x10000, 0x20000, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let areas = gen_areas(g * g, &net);
+
+        let randomizer = RwDirectionalRandomizer;
+        let mut dht = CoordDht::new(&net, &coords, &landmarks, &areas, &randomizer, 2, 0x1337);
+        // Hashing a key is deterministic:
+        assert!(dht.key_coord(b"hello") == dht.key_coord(b"hello"));
+        assert!(dht.key_coord(b"hello") != dht.key_coord(b"world"));
+
+        let stored = dht.store(b"hello", 0, &mut rng);
+        assert!(dht.load()[stored.node] == 1);
+        assert!(dht.load().iter().sum::<usize>() == 1);
+
+        // Looking up from the storing node itself always succeeds:
+        let op = dht.lookup(b"hello", stored.node, &mut rng);
+        assert!(op.found);
+        assert!(op.node == stored.node);
+
+        assert!(!dht.lookup(b"world", 0, &mut rng).found);
+    }
+
+    #[test]
+    fn test_key_coord_independent_dhts() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        let net = gen_network(0, g, 2 * g + 1, 0x10000, 0x20000, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let areas = gen_areas(g * g, &net);
+
+        // Two nodes, each holding its own copy of the landmarks coordinates:
+        let (coords_a, coords_b) = (coords.clone(), coords.clone());
+        let randomizer = RwDirectionalRandomizer;
+        let dht_a = CoordDht::new(&net, &coords_a, &landmarks, &areas, &randomizer, 2, 0x1337);
+        let dht_b = CoordDht::new(&net, &coords_b, &landmarks, &areas, &randomizer, 2, 0x1337);
+        let space = CoordSpace::new(&landmarks, &coords);
+        for key in &[&b""[..], b"hello", b"world", &[0xff; 40][..]] {
+            assert!(dht_a.key_coord(key) == dht_b.key_coord(key));
+            assert!(dht_a.key_coord(key) == hash_key_to_coord(&randomizer, &space, key, 0x1337));
+        }
+        // The salt changes the coordinates of keys:
+        let dht_c = CoordDht::new(&net, &coords, &landmarks, &areas, &randomizer, 2, 0x1338);
+        assert!(dht_a.key_coord(b"hello") != dht_c.key_coord(b"hello"));
+    }
+
+    #[test]
+    fn test_eval_coord_dht() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 7;
+        let net = gen_network(0, g, 2 * g + 1, 0x10000, 0x20000, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let areas = gen_areas(g * g, &net);
+
+        let randomizer = RwDirectionalRandomizer;
+        let mut dht = CoordDht::new(&net, &coords, &landmarks, &areas, &randomizer, 2, 0x1337);
+        let eval = eval_coord_dht(&mut dht, 50, 4, &mut rng);
+        assert!(eval.lookup_success_rate > 0.5 && eval.lookup_success_rate <= 1.0);
+        assert!(dht.load().iter().sum::<usize>() == 50);
+        assert!(eval.max_load_ratio >= 1.0);
+    }
+}
@@ -0,0 +1,199 @@
+extern crate rand;
+extern crate ring;
+
+use self::rand::{Rng};
+use self::ring::digest;
+
+use landmarks::coord_randomizers::{CoordRandomizer, CoordSpace};
+
+/// Encode a u64 number as 8 bytes (Big endian).
+fn encode_u64(x: u64) -> Vec<u8> {
+    (0 .. 8).rev()
+        .map(|i| ((x >> (8 * i)) & 0xff) as u8)
+        .collect()
+}
+
+/// A deterministic stream of random looking bytes, derived from a key and a salt.
+/// Block number i of the stream is sha256("hash_rng" || salt || len(key) || key || i).
+/// Two instances created with the same key and salt always produce the same stream.
+pub struct HashRng {
+    prefix: Vec<u8>,
+    counter: u64,
+    /// Unused bytes of the current block.
+    block: Vec<u8>,
+}
+
+impl HashRng {
+    pub fn new(key: &[u8], salt: u64) -> HashRng {
+        let mut prefix: Vec<u8> = Vec::new();
+        prefix.extend_from_slice(b"hash_rng");
+        prefix.extend(encode_u64(salt));
+        prefix.extend(encode_u64(key.len() as u64));
+        prefix.extend_from_slice(key);
+
+        HashRng {
+            prefix,
+            counter: 0,
+            block: Vec::new(),
+        }
+    }
+
+    /// Calculate the next block of the stream.
+    fn next_block(&mut self) {
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(&self.prefix);
+        ctx.update(&encode_u64(self.counter));
+        self.block = ctx.finish().as_ref().to_vec();
+        // Bytes are taken from the end of the block:
+        self.block.reverse();
+        self.counter += 1;
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        if self.block.is_empty() {
+            self.next_block();
+        }
+        self.block.pop().unwrap()
+    }
+}
+
+impl Rng for HashRng {
+    fn next_u32(&mut self) -> u32 {
+        (0 .. 4).fold(0, |res, _| (res << 8) | (self.next_byte() as u32))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (0 .. 8).fold(0, |res, _| (res << 8) | (self.next_byte() as u64))
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            *byte = self.next_byte();
+        }
+    }
+}
+
+/// Deterministically hash a key into a coordinate, using the given randomizer.
+/// Any two callers with the same landmarks coordinates, randomizer, key and salt
+/// get the same coordinate.
+pub fn hash_key_to_coord(randomizer: &dyn CoordRandomizer, space: &CoordSpace,
+                         key: &[u8], salt: u64) -> Vec<u64> {
+    let mut hash_rng = HashRng::new(key, salt);
+    randomizer.randomize(space, &mut hash_rng)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network};
+    use landmarks::coords::{build_coords, choose_landmarks};
+    use landmarks::coord_randomizers::{LandmarksCoordsRandomizer};
+
+    #[test]
+    fn test_hash_rng_deterministic() {
+        let mut rng_a = HashRng::new(b"key", 1);
+        let mut rng_b = HashRng::new(b"key", 1);
+        let stream_a = (0 .. 100).map(|_| rng_a.next_u64()).collect::<Vec<u64>>();
+        let stream_b = (0 .. 100).map(|_| rng_b.next_u64()).collect::<Vec<u64>>();
+        assert!(stream_a == stream_b);
+
+        // Different salt or key give a different stream:
+        assert!(HashRng::new(b"key", 2).next_u64() != stream_a[0]);
+        assert!(HashRng::new(b"kez", 1).next_u64() != stream_a[0]);
+        // The key length is part of the hashed data:
+        assert!(HashRng::new(b"", 1).next_u64() != HashRng::new(b"\0", 1).next_u64());
+    }
+
+    #[test]
+    fn test_hash_rng_pinned() {
+        // Calculated independently: sha256(b"hash_rng" || salt || len(key) || key || counter),
+        // with all numbers as 8 big endian bytes.
+        let mut hash_rng = HashRng::new(b"hello", 0x1337);
+        assert_eq!(hash_rng.next_u64(), 0x241cd7de35cd0cfa);
+        assert_eq!(hash_rng.next_u64(), 0xe233267810393c2d);
+        hash_rng.next_u64();
+        hash_rng.next_u64();
+        // First 8 bytes of the second block:
+        assert_eq!(hash_rng.next_u64(), 0xacedad6537cd9aa4);
+    }
+
+    #[test]
+    fn test_hash_key_to_coord_pinned() {
+        // Every entry is distinct, so the coordinate shows which entries were chosen:
+        let landmarks = vec![0, 1, 2, 3];
+        let coords = (0 .. 4)
+            .map(|i| (0 .. 4).map(|j| 10 * i + j).collect::<Vec<u64>>())
+            .collect::<Vec<Vec<u64>>>();
+        let space = CoordSpace::new(&landmarks, &coords);
+        let coord = hash_key_to_coord(&LandmarksCoordsRandomizer, &space, b"hello", 0x1337);
+        // Calculated independently from the sha256 stream (See test_hash_rng_pinned):
+        // Every entry is coords[landmarks[u % 4]][u' % 4] for two consecutive u64 values.
+        assert_eq!(coord, vec![21, 3, 2, 20]);
+    }
+
+    /// Kolmogorov-Smirnov statistic of two samples. Equal values are consumed together,
+    /// so discrete samples are handled correctly.
+    fn ks_statistic(sample_a: &[u64], sample_b: &[u64]) -> f64 {
+        let mut a = sample_a.to_vec();
+        let mut b = sample_b.to_vec();
+        a.sort();
+        b.sort();
+
+        let (mut i, mut j) = (0, 0);
+        let mut max_diff: f64 = 0.0;
+        while i < a.len() && j < b.len() {
+            let x = a[i].min(b[j]);
+            while i < a.len() && a[i] == x {
+                i += 1;
+            }
+            while j < b.len() && b[j] == x {
+                j += 1;
+            }
+            let diff = (i as f64) / (a.len() as f64) - (j as f64) / (b.len() as f64);
+            max_diff = max_diff.max(diff.abs());
+        }
+        max_diff
+    }
+
+    #[test]
+    fn test_hash_key_to_coord_distribution() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        let net = gen_network(0, g, 2 * g + 1, 0x10000, 0x20000, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let space = CoordSpace::new(&landmarks, &coords);
+        let randomizer = LandmarksCoordsRandomizer;
+
+        // Compare the distribution of every entry, between rng driven and hash driven
+        // coordinates, using a two sample Kolmogorov-Smirnov test:
+        let num_coords: usize = 4000;
+        let mut rng_entries = vec![Vec::new(); landmarks.len()];
+        let mut hash_entries = vec![Vec::new(); landmarks.len()];
+        for key in 0 .. num_coords {
+            let rng_coord = randomizer.randomize(&space, &mut rng);
+            let hash_coord = hash_key_to_coord(&randomizer, &space, &encode_u64(key as u64), 0);
+            for i in 0 .. landmarks.len() {
+                rng_entries[i].push(rng_coord[i]);
+                hash_entries[i].push(hash_coord[i]);
+            }
+        }
+        // Critical value of the two sample test, for significance level 0.001:
+        let critical = 1.95 * (2.0 / (num_coords as f64)).sqrt();
+        for i in 0 .. landmarks.len() {
+            let ks = ks_statistic(&rng_entries[i], &hash_entries[i]);
+            assert!(ks < critical, "entry {}: ks={}", i, ks);
+        }
+
+        // Uniformity of small ranges:
+        let mut hash_rng = HashRng::new(b"uniform", 0);
+        let mut counts = vec![0_usize; 8];
+        for _ in 0 .. 8000 {
+            counts[(hash_rng.next_u32() % 8) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| count > 850 && count < 1150));
+    }
+}
@@ -118,6 +118,27 @@ pub fn max_dist(a: &Vec<u64>, b: &Vec<u64>) -> u64 {
 }
 
 
+/// Like max_dist, but for coordinates with optional entries.
+/// Entries that are missing in any of the coordinates are ignored.
+/// Returns None if the coordinates have no entries in common.
+pub fn partial_max_dist(a: &Vec<Option<u64>>, b: &Vec<Option<u64>>) -> Option<u64> {
+    assert!(a.len() == b.len(), "Coordinates have different amount of entries! aborting.");
+    a.iter()
+        .zip(b)
+        .filter_map(|(&u_opt, &v_opt)| match (u_opt, v_opt) {
+            (Some(u), Some(v)) => Some(dist_u64(u,v)),
+            _ => None,
+        })
+        .max()
+}
+
+/// Approximate distance between two nodes in the network using network coordinates
+/// with optional entries.
+pub fn approx_partial_max_dist(u: usize, v: usize, pcoords: &Vec<Vec<Option<u64>>>) 
+    -> Option<u64> {
+    partial_max_dist(&pcoords[u], &pcoords[v])
+}
+
 /// Approximate distance between two nodes in the network using network coordinates
 pub fn approx_max_dist(u: usize, v: usize, coords: &Vec<Vec<u64>>, landmarks: &Vec<usize>) 
     -> u64 {
@@ -0,0 +1,258 @@
+extern crate rand;
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+
+use landmarks::coord_mappers::{max_dist};
+use landmarks::randomize_coord::{randomize_coord_rand_coeffs, randomize_coord_landmarks_coords,
+    randomize_coord_fair_cuts, randomize_coord_cheat, randomize_coord_rw,
+    randomize_coord_rw_sparse, randomize_coord_rw_directional, randomize_coord_rw_mix,
+    calc_upper_constraints};
+
+/// Everything a randomizer may need to know about the landmarks coordinates.
+pub struct CoordSpace<'a> {
+    pub landmarks: &'a Vec<usize>,
+    pub coords: &'a Vec<Vec<u64>>,
+    /// See calc_upper_constraints. Used by the random walk randomizers.
+    pub upper_constraints: Vec<u64>,
+}
+
+impl<'a> CoordSpace<'a> {
+    pub fn new(landmarks: &'a Vec<usize>, coords: &'a Vec<Vec<u64>>) -> Self {
+        CoordSpace {
+            landmarks,
+            coords,
+            upper_constraints: calc_upper_constraints(landmarks, coords),
+        }
+    }
+}
+
+/// A method for generating a random coordinate in the space of landmarks coordinates.
+pub trait CoordRandomizer {
+    /// Short name of the method, used for choosing a randomizer and printing results.
+    fn name(&self) -> &str;
+
+    /// Generate a random coordinate.
+    fn randomize(&self, space: &CoordSpace, rng: &mut dyn Rng) -> Vec<u64>;
+}
+
+/// See randomize_coord_rand_coeffs
+pub struct RandCoeffsRandomizer;
+
+/// See randomize_coord_landmarks_coords
+pub struct LandmarksCoordsRandomizer;
+
+/// See randomize_coord_fair_cuts
+pub struct FairCutsRandomizer;
+
+/// See randomize_coord_cheat
+pub struct CheatRandomizer {
+    /// Maximum drift from the coordinate of the chosen node.
+    pub noise_size: u64,
+}
+
+/// See randomize_coord_rw
+pub struct RwRandomizer;
+
+/// See randomize_coord_rw_sparse
+pub struct RwSparseRandomizer;
+
+/// See randomize_coord_rw_directional
+pub struct RwDirectionalRandomizer;
+
+/// See randomize_coord_rw_mix
+pub struct RwMixRandomizer;
+
+
+impl CoordRandomizer for RandCoeffsRandomizer {
+    fn name(&self) -> &str {
+        "rand_coeffs"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_rand_coeffs(space.landmarks, space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for LandmarksCoordsRandomizer {
+    fn name(&self) -> &str {
+        "landmarks_coords"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_landmarks_coords(space.landmarks, space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for FairCutsRandomizer {
+    fn name(&self) -> &str {
+        "fair_cuts"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_fair_cuts(space.landmarks, space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for CheatRandomizer {
+    fn name(&self) -> &str {
+        "cheat"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_cheat(self.noise_size, space.landmarks, space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for RwRandomizer {
+    fn name(&self) -> &str {
+        "rw"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_rw(&space.upper_constraints, space.landmarks, space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for RwSparseRandomizer {
+    fn name(&self) -> &str {
+        "rw_sparse"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_rw_sparse(&space.upper_constraints, space.landmarks, space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for RwDirectionalRandomizer {
+    fn name(&self) -> &str {
+        "rw_directional"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_rw_directional(&space.upper_constraints, space.landmarks,
+                                       space.coords, &mut rng)
+    }
+}
+
+impl CoordRandomizer for RwMixRandomizer {
+    fn name(&self) -> &str {
+        "rw_mix"
+    }
+
+    fn randomize(&self, space: &CoordSpace, mut rng: &mut dyn Rng) -> Vec<u64> {
+        randomize_coord_rw_mix(&space.upper_constraints, space.landmarks, space.coords, &mut rng)
+    }
+}
+
+/// All randomizers, with their default parameters.
+pub fn coord_randomizers() -> Vec<Box<dyn CoordRandomizer>> {
+    vec![
+        Box::new(RandCoeffsRandomizer),
+        Box::new(LandmarksCoordsRandomizer),
+        Box::new(FairCutsRandomizer),
+        Box::new(CheatRandomizer {noise_size: 0x10000}),
+        Box::new(RwRandomizer),
+        Box::new(RwSparseRandomizer),
+        Box::new(RwDirectionalRandomizer),
+        Box::new(RwMixRandomizer),
+    ]
+}
+
+/// Choose a randomizer (With default parameters) by name.
+/// Returns None if there is no randomizer with the given name.
+pub fn coord_randomizer_by_name(name: &str) -> Option<Box<dyn CoordRandomizer>> {
+    coord_randomizers().into_iter()
+        .find(|randomizer| randomizer.name() == name)
+}
+
+/// How evenly random coordinates are spread over the nodes of the network.
+#[derive(Debug)]
+pub struct BalanceEval {
+    /// Maximum amount of random coordinates that were mapped to the same node.
+    pub max_nr: usize,
+    /// Average amount of nodes that are closest to a random coordinate (Ties).
+    pub average_min_indices: f64,
+    /// Fraction of nodes that no random coordinate was mapped to.
+    pub unreached_frac: f64,
+}
+
+/// Generate num_coords random coordinates, and map every coordinate to the closest node
+/// (By max_dist). Ties are broken randomly.
+pub fn eval_balance<R: Rng>(randomizer: &dyn CoordRandomizer, space: &CoordSpace,
+                            num_coords: usize, rng: &mut R) -> BalanceEval {
+
+    let num_nodes = space.coords.len();
+    let mut node_repeats: Vec<usize> = vec![0; num_nodes];
+    let mut sum_min_indices: usize = 0;
+
+    for _ in 0 .. num_coords {
+        let rcoord = randomizer.randomize(space, rng);
+        let min_value = space.coords.iter()
+            .map(|coord| max_dist(&rcoord, coord))
+            .min().unwrap();
+
+        let min_indices = (0 .. num_nodes)
+            .filter(|&i| max_dist(&rcoord, &space.coords[i]) == min_value)
+            .collect::<Vec<usize>>();
+        sum_min_indices += min_indices.len();
+
+        let choice_range: Range<usize> = Range::new(0, min_indices.len());
+        node_repeats[min_indices[choice_range.ind_sample(rng)]] += 1;
+    }
+
+    let num_unreached = node_repeats.iter().filter(|&&nr| nr == 0).count();
+    BalanceEval {
+        max_nr: node_repeats.into_iter().max().unwrap(),
+        average_min_indices: (sum_min_indices as f64) / (num_coords as f64),
+        unreached_frac: (num_unreached as f64) / (num_nodes as f64),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network};
+    use landmarks::coords::{build_coords, choose_landmarks};
+
+    #[test]
+    fn test_coord_randomizer_by_name() {
+        let randomizers = coord_randomizers();
+        let mut names = randomizers.iter()
+            .map(|randomizer| randomizer.name().to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        assert!(names.len() == randomizers.len());
+
+        for name in &names {
+            assert!(coord_randomizer_by_name(name).unwrap().name() == name);
+        }
+        assert!(coord_randomizer_by_name("no_such_randomizer").is_none());
+    }
+
+    #[test]
+    fn test_eval_balance_all_randomizers() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        // Large weights are required for random walk coordinates:
+        let net = gen_network(0, g, 2 * g + 1, 0x10000, 0x20000, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let space = CoordSpace::new(&landmarks, &coords);
+
+        let num_coords = 64;
+        for randomizer in coord_randomizers() {
+            let rcoord = randomizer.randomize(&space, &mut rng);
+            assert!(rcoord.len() == landmarks.len());
+
+            let eval = eval_balance(&*randomizer, &space, num_coords, &mut rng);
+            assert!(eval.max_nr >= 1 && eval.max_nr <= num_coords);
+            assert!(eval.average_min_indices >= 1.0);
+            assert!(eval.unreached_frac >= 0.0 && eval.unreached_frac < 1.0);
+        }
+    }
+}
@@ -1,11 +1,20 @@
 extern crate rand;
+extern crate petgraph;
 
-use std::collections::HashSet;
+use std::collections::{HashSet, HashMap, BTreeMap};
+use std::hash::Hash;
+use std::time::Instant;
+use std::mem::size_of;
+use std::cmp::Reverse;
 
 use self::rand::Rng;
+use self::petgraph::algo::{dijkstra};
+use self::petgraph::visit::{EdgeRef};
 
 use network::{Network};
 use random_util::choose_k_nums;
+use convergence::{ConvergenceObserver, NullObserver, IterStats};
+use state_size::{NodeStateSize};
 
 
 pub fn choose_landmarks<R: Rng, Node> 
@@ -19,9 +28,13 @@ pub fn choose_landmarks<R: Rng, Node>
 }
 
 
-fn iter_coords<Node>(net: &Network<Node>, work_coords: &mut Vec<Vec<Option<u64>>>) -> bool {
-    let mut has_changed = false;
+/// Perform one iteration of coordinates calculation.
+/// Returns (nodes_changed, entries_changed)
+fn iter_coords<Node>(net: &Network<Node>, work_coords: &mut Vec<Vec<Option<u64>>>) -> (usize, usize) {
+    let mut nodes_changed = 0;
+    let mut entries_changed = 0;
     for v in net.igraph.nodes() {
+        let mut has_changed = false;
         for (v,nei,&weight) in net.igraph.edges(v) {
             for c in 0..work_coords[nei].len() {
                 let dist = work_coords[nei][c];
@@ -32,22 +45,65 @@ fn iter_coords<Node>(net: &Network<Node>, work_coords: &mut Vec<Vec<Option<u64>>
                 if work_coords[v][c].is_none() {
                     work_coords[v][c] = Some(cdist);
                     has_changed = true;
+                    entries_changed += 1;
                     continue
                 }
                 if work_coords[v][c].unwrap() > cdist {
                     work_coords[v][c] = Some(cdist);
                     has_changed = true;
+                    entries_changed += 1;
                 }
             }
         }
+        if has_changed {
+            nodes_changed += 1;
+        }
     }
-    has_changed
+    (nodes_changed, entries_changed)
 }
 
 /// Every node asks neighbours about distance to landmarks and 
 /// updates his own distances accordingly.
-/// Returns true if anything in the coords state has changed.
+/// Returns None if some node can not reach some landmark.
 pub fn build_coords<Node>(net: &Network<Node>, landmarks:&Vec<usize>) -> Option<Vec<Vec<u64>>> 
+{
+    build_coords_observed(net, landmarks, &mut NullObserver)
+}
+
+/// Every node asks neighbours about distance to landmarks and 
+/// updates his own distances accordingly.
+/// observer is notified after every iteration.
+pub fn build_coords_observed<Node, O: ConvergenceObserver>(net: &Network<Node>, 
+                     landmarks:&Vec<usize>, observer: &mut O) -> Option<Vec<Vec<u64>>> 
+{
+    let work_coords = build_partial_coords_observed(net, landmarks, observer);
+
+    let is_disconnected: bool = 
+        work_coords.iter().any(|coord| 
+               coord.iter().any(|&c_opt| c_opt.is_none()));
+
+    if is_disconnected {
+        return None;
+    }
+
+    Some(work_coords.into_iter().map(|coord_opt| 
+            coord_opt.into_iter().map(|c_opt| c_opt.unwrap()).collect::<Vec<_>>())
+            .collect::<Vec<_>>())
+    
+}
+
+/// Build coordinates with optional entries.
+/// An entry is None if the node can not reach the corresponding landmark.
+pub fn build_partial_coords<Node>(net: &Network<Node>, 
+                     landmarks:&Vec<usize>) -> Vec<Vec<Option<u64>>> 
+{
+    build_partial_coords_observed(net, landmarks, &mut NullObserver)
+}
+
+/// Same as build_coords_observed, but keeps entries of unreachable landmarks as None
+/// instead of failing.
+pub fn build_partial_coords_observed<Node, O: ConvergenceObserver>(net: &Network<Node>, 
+                     landmarks:&Vec<usize>, observer: &mut O) -> Vec<Vec<Option<u64>>> 
 {
 
     let mut work_coords: Vec<Vec<Option<u64>>> = Vec::new();
@@ -65,27 +121,41 @@ pub fn build_coords<Node>(net: &Network<Node>, landmarks:&Vec<usize>) -> Option<
         work_coords.push(v_coords);
     }
 
-    // println!("");
-    let mut has_changed = true;
-    while has_changed {
-        has_changed = iter_coords(net, &mut work_coords);
-        // println!("Iter");
-        // print!(".");
+    let mut iter = 0;
+    loop {
+        let start = Instant::now();
+        let (nodes_changed, entries_changed) = iter_coords(net, &mut work_coords);
+        observer.on_iter(&IterStats {
+            iter,
+            nodes_changed,
+            entries_changed,
+            skipped_updates: 0,
+            elapsed: start.elapsed(),
+        });
+        if entries_changed == 0 {
+            break;
+        }
+        iter += 1;
     }
-    // println!("");
+    work_coords
+}
 
-    let is_disconnected: bool = 
-        work_coords.iter().any(|coord| 
-               coord.iter().any(|&c_opt| c_opt.is_none()));
+/// Convert full coordinates to coordinates with optional entries.
+pub fn to_partial_coords(coords: &Vec<Vec<u64>>) -> Vec<Vec<Option<u64>>> {
+    coords.iter()
+        .map(|coord| coord.iter().map(|&c| Some(c)).collect())
+        .collect()
+}
 
-    if is_disconnected {
-        return None;
+/// Forget the entries of some landmarks in all coordinates.
+/// landmark_positions are positions inside the landmarks vector (Not node indices).
+pub fn drop_landmark_entries(pcoords: &mut Vec<Vec<Option<u64>>>, 
+                             landmark_positions: &Vec<usize>) {
+    for pcoord in pcoords.iter_mut() {
+        for &pos in landmark_positions {
+            pcoord[pos] = None;
+        }
     }
-
-    Some(work_coords.into_iter().map(|coord_opt| 
-            coord_opt.into_iter().map(|c_opt| c_opt.unwrap()).collect::<Vec<_>>())
-            .collect::<Vec<_>>())
-    
 }
 
 
@@ -103,6 +173,105 @@ pub fn is_coord_unique(coords: &Vec<Vec<u64>>) -> bool {
     true
 }
 
+/// A group of nodes that share the same coordinate.
+#[derive(Debug)]
+pub struct CollisionGroup {
+    pub coord: Vec<u64>,
+    /// Indices of all nodes with this coordinate, sorted.
+    pub nodes: Vec<usize>,
+    /// Graph distances between every pair of nodes in the group: (node_a, node_b, dist).
+    pub pair_dists: Vec<(usize, usize, u64)>,
+}
+
+impl CollisionGroup {
+    /// Maximum graph distance between two nodes in the group.
+    pub fn max_dist(&self) -> u64 {
+        self.pair_dists.iter()
+            .map(|&(_, _, dist)| dist)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+pub struct CollisionReport {
+    /// multiplicity -> Amount of distinct coordinates shared by exactly multiplicity nodes.
+    pub multiplicity_hist: BTreeMap<usize, usize>,
+    /// Largest groups of colliding nodes, ordered by decreasing size.
+    pub largest_groups: Vec<CollisionGroup>,
+}
+
+impl CollisionReport {
+    /// Check if all coordinates are unique.
+    pub fn is_unique(&self) -> bool {
+        self.multiplicity_hist.keys().all(|&multiplicity| multiplicity <= 1)
+    }
+
+    /// Amount of nodes that share their coordinate with at least one other node.
+    pub fn num_colliding_nodes(&self) -> usize {
+        self.multiplicity_hist.iter()
+            .filter(|&(&multiplicity, _)| multiplicity > 1)
+            .map(|(&multiplicity, &count)| multiplicity * count)
+            .sum()
+    }
+}
+
+/// Find all groups of nodes that share the same coordinate.
+/// Returns a report with the histogram of coordinate multiplicities, and the
+/// max_groups largest colliding groups, together with the graph distances inside every group.
+pub fn collision_report<Node: Hash + Eq + Clone>(net: &Network<Node>, coords: &Vec<Vec<u64>>, 
+                                              max_groups: usize) -> CollisionReport {
+
+    let mut coord_nodes: HashMap<&Vec<u64>, Vec<usize>> = HashMap::new();
+    for (node, coord) in coords.iter().enumerate() {
+        coord_nodes.entry(coord).or_insert_with(Vec::new).push(node);
+    }
+
+    let mut multiplicity_hist: BTreeMap<usize, usize> = BTreeMap::new();
+    for nodes in coord_nodes.values() {
+        *multiplicity_hist.entry(nodes.len()).or_insert(0) += 1;
+    }
+
+    let mut groups = coord_nodes.into_iter()
+        .filter(|&(_, ref nodes)| nodes.len() > 1)
+        .collect::<Vec<_>>();
+    // Largest groups first. Ties are broken by the smallest node, for determinism:
+    groups.sort_by_key(|&(_, ref nodes)| (Reverse(nodes.len()), nodes[0]));
+    groups.truncate(max_groups);
+
+    let largest_groups = groups.into_iter()
+        .map(|(coord, nodes)| {
+            let mut pair_dists = Vec::new();
+            for i in 0 .. nodes.len() {
+                // One single source search for all the pairs of nodes[i]:
+                let dists = dijkstra(&net.igraph, nodes[i], None, |e| *e.weight());
+                for j in (i + 1) .. nodes.len() {
+                    pair_dists.push((nodes[i], nodes[j], dists[&nodes[j]]));
+                }
+            }
+            CollisionGroup {
+                coord: coord.clone(),
+                nodes,
+                pair_dists,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    CollisionReport {
+        multiplicity_hist,
+        largest_groups,
+    }
+}
+
+/// Size of the coordinate every node keeps: One distance entry for every landmark.
+pub fn coords_state_sizes(coords: &Vec<Vec<u64>>) -> Vec<NodeStateSize> {
+    coords.iter()
+        .map(|coord| NodeStateSize {
+            entries: coord.len(),
+            bytes: coord.len() * size_of::<u64>(),
+        })
+        .collect()
+}
+
 
 /*
 /// Print some coordinates
@@ -132,4 +301,61 @@ mod tests {
         assert!(my_set.contains(&vec![1,2,3]));
         assert!(!my_set.contains(&vec![1,2,4]));
     }
+
+    #[test]
+    fn test_collision_report() {
+        // A star: 0 is connected to 1,2,3. 3 is connected to 4.
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 5 {
+            net.add_node(x);
+        }
+        for x in 1 .. 4 {
+            net.igraph.add_edge(0, x, 1);
+        }
+        net.igraph.add_edge(3, 4, 1);
+
+        // A single landmark at the center of the star:
+        let coords = build_coords(&net, &vec![0]).unwrap();
+        assert!(!is_coord_unique(&coords));
+
+        let report = collision_report(&net, &coords, 10);
+        assert!(!report.is_unique());
+        assert!(report.multiplicity_hist[&1] == 2);
+        assert!(report.multiplicity_hist[&3] == 1);
+        assert!(report.num_colliding_nodes() == 3);
+        assert!(report.largest_groups.len() == 1);
+        assert!(report.largest_groups[0].nodes == vec![1,2,3]);
+        assert!(report.largest_groups[0].pair_dists.len() == 3);
+        assert!(report.largest_groups[0].max_dist() == 2);
+
+        let coords = build_coords(&net, &vec![1, 2, 4]).unwrap();
+        assert!(collision_report(&net, &coords, 10).is_unique());
+    }
+
+    #[test]
+    fn test_build_partial_coords() {
+        // Two separate components: {0,1,2} and {3,4}
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 5 {
+            net.add_node(x);
+        }
+        net.igraph.add_edge(0, 1, 1);
+        net.igraph.add_edge(1, 2, 3);
+        net.igraph.add_edge(3, 4, 2);
+
+        let landmarks = vec![0, 4];
+        assert!(build_coords(&net, &landmarks).is_none());
+        let pcoords = build_partial_coords(&net, &landmarks);
+        assert!(pcoords == vec![vec![Some(0), None], vec![Some(1), None], vec![Some(4), None],
+                                vec![None, Some(2)], vec![None, Some(0)]]);
+
+        // For a connected network the partial coordinates are the full coordinates:
+        net.igraph.add_edge(2, 3, 1);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let mut pcoords = build_partial_coords(&net, &landmarks);
+        assert!(pcoords == to_partial_coords(&coords));
+
+        drop_landmark_entries(&mut pcoords, &vec![0]);
+        assert!(pcoords.iter().all(|pcoord| pcoord[0].is_none() && pcoord[1].is_some()));
+    }
 }
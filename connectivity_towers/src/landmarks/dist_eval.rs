@@ -0,0 +1,227 @@
+extern crate rand;
+
+use std::hash::Hash;
+use std::cmp::{min};
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+
+use network::{Network};
+use statistic::{pearson, spearman};
+use landmarks::coord_mappers::{approx_max_dist, approx_avg_dist,
+    approx_pairs_dist1, approx_pairs_dist2,
+    approx_pairs_dist1_normalized, approx_pairs_dist2_normalized, dist_u64};
+
+/// A function that approximates distance between two nodes using landmarks coordinates.
+pub type DistMapper = fn(usize, usize, &Vec<Vec<u64>>, &Vec<usize>) -> f64;
+
+/// All distance mappers from coord_mappers, with their names.
+pub fn dist_mappers() -> Vec<(&'static str, DistMapper)> {
+    vec![
+        ("max", |u, v, coords, landmarks| approx_max_dist(u, v, coords, landmarks) as f64),
+        ("avg", approx_avg_dist),
+        ("pairs1", approx_pairs_dist1),
+        ("pairs2", approx_pairs_dist2),
+        ("pairs1_norm", approx_pairs_dist1_normalized),
+        ("pairs2_norm", approx_pairs_dist2_normalized),
+    ]
+}
+
+/// A pair of nodes, together with the real distance between them.
+pub struct NodePair {
+    pub u: usize,
+    pub v: usize,
+    pub dist: u64,
+}
+
+/// Quality of distance estimates of one mapper over a set of node pairs.
+#[derive(Debug)]
+pub struct MapperEval {
+    pub name: &'static str,
+    /// Relative error: |estimate - dist| / dist
+    pub mean_rel_error: f64,
+    pub median_rel_error: f64,
+    pub p90_rel_error: f64,
+    pub max_rel_error: f64,
+    /// Fraction of pairs where the estimate is below the landmarks lower bound.
+    /// None if landmarks coordinates were not given.
+    pub below_lower_frac: Option<f64>,
+    /// Fraction of pairs where the estimate is above the landmarks upper bound.
+    /// None if landmarks coordinates were not given.
+    pub above_upper_frac: Option<f64>,
+    pub pearson: f64,
+    pub spearman: f64,
+}
+
+/// Sample num_pairs pairs of distinct nodes, and calculate the real distance between them.
+pub fn sample_pairs<R: Rng, Node: Hash + Eq + Clone>(net: &Network<Node>, num_pairs: usize,
+                                                  rng: &mut R) -> Vec<NodePair> {
+    let num_nodes = net.igraph.node_count();
+    assert!(num_nodes > 1, "Not enough nodes to sample pairs!");
+    let node_range: Range<usize> = Range::new(0, num_nodes);
+
+    let mut pairs: Vec<NodePair> = Vec::new();
+    while pairs.len() < num_pairs {
+        let u = node_range.ind_sample(rng);
+        let v = node_range.ind_sample(rng);
+        if u == v {
+            continue;
+        }
+        if let Some(dist) = net.dist(u, v) {
+            pairs.push(NodePair {u, v, dist});
+        }
+    }
+    pairs
+}
+
+/// Lower bound for the distance between u and v, by the triangle inequality over all landmarks.
+pub fn landmarks_lower_bound(u: usize, v: usize, coords: &Vec<Vec<u64>>) -> u64 {
+    (0 .. coords[u].len())
+        .map(|i| dist_u64(coords[u][i], coords[v][i]))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Upper bound for the distance between u and v: The length of the path through the best
+/// landmark.
+pub fn landmarks_upper_bound(u: usize, v: usize, coords: &Vec<Vec<u64>>) -> Option<u64> {
+    (0 .. coords[u].len())
+        .map(|i| coords[u][i] + coords[v][i])
+        .min()
+}
+
+/// Get the value at quantile q of sorted values. (Nearest rank)
+fn quantile(sorted_vals: &Vec<f64>, q: f64) -> f64 {
+    let rank = ((sorted_vals.len() as f64) * q).ceil() as usize;
+    sorted_vals[min(rank.max(1), sorted_vals.len()) - 1]
+}
+
+/// Evaluate the quality of distance estimates of one mapper over the given pairs.
+pub fn eval_mapper(name: &'static str, mapper: DistMapper, pairs: &Vec<NodePair>,
+                   coords: &Vec<Vec<u64>>, landmarks: &Vec<usize>) -> MapperEval {
+
+    let node_dist = |u, v| mapper(u, v, coords, landmarks);
+    eval_dist_fn(name, node_dist, pairs, Some(coords))
+}
+
+/// Evaluate the quality of any approximate distance function over the given pairs.
+/// Violations of the landmarks bounds are calculated only if bounds_coords (Landmarks
+/// coordinates) are given.
+pub fn eval_dist_fn<F>(name: &'static str, node_dist: F, pairs: &Vec<NodePair>,
+                       bounds_coords: Option<&Vec<Vec<u64>>>) -> MapperEval 
+    where F: Fn(usize, usize) -> f64 {
+
+    assert!(pairs.len() > 0, "No pairs to evaluate!");
+
+    let mut estimates: Vec<f64> = Vec::new();
+    let mut dists: Vec<f64> = Vec::new();
+    let mut rel_errors: Vec<f64> = Vec::new();
+    let mut below_lower: usize = 0;
+    let mut above_upper: usize = 0;
+
+    for pair in pairs {
+        let estimate = node_dist(pair.u, pair.v);
+        let dist = pair.dist as f64;
+
+        if let Some(coords) = bounds_coords {
+            if estimate < landmarks_lower_bound(pair.u, pair.v, coords) as f64 {
+                below_lower += 1;
+            }
+            if let Some(upper_bound) = landmarks_upper_bound(pair.u, pair.v, coords) {
+                if estimate > upper_bound as f64 {
+                    above_upper += 1;
+                }
+            }
+        }
+
+        estimates.push(estimate);
+        dists.push(dist);
+        rel_errors.push((estimate - dist).abs() / dist);
+    }
+
+    rel_errors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let num_pairs = pairs.len() as f64;
+    let bounds_frac = |count: usize| bounds_coords.map(|_| (count as f64) / num_pairs);
+
+    MapperEval {
+        name,
+        mean_rel_error: rel_errors.iter().sum::<f64>() / num_pairs,
+        median_rel_error: quantile(&rel_errors, 0.5),
+        p90_rel_error: quantile(&rel_errors, 0.9),
+        max_rel_error: rel_errors[rel_errors.len() - 1],
+        below_lower_frac: bounds_frac(below_lower),
+        above_upper_frac: bounds_frac(above_upper),
+        pearson: pearson(&estimates, &dists).unwrap(),
+        spearman: spearman(&estimates, &dists).unwrap(),
+    }
+}
+
+/// Evaluate all mappers from dist_mappers over the given pairs.
+pub fn eval_all_mappers(pairs: &Vec<NodePair>, coords: &Vec<Vec<u64>>,
+                        landmarks: &Vec<usize>) -> Vec<MapperEval> {
+    dist_mappers().into_iter()
+        .map(|(name, mapper)| eval_mapper(name, mapper, pairs, coords, landmarks))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network_kind, NetworkKind};
+    use landmarks::coords::{build_coords, choose_landmarks};
+
+    #[test]
+    fn test_landmarks_bounds() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        for kind in NetworkKind::all() {
+            let net = gen_network_kind(kind, g, 2 * g + 1, 1, 3, &mut rng);
+            let landmarks = choose_landmarks(&net, g, &mut rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+
+            // Real distances never violate the bounds:
+            for pair in sample_pairs(&net, 100, &mut rng) {
+                assert!(landmarks_lower_bound(pair.u, pair.v, &coords) <= pair.dist);
+                assert!(landmarks_upper_bound(pair.u, pair.v, &coords).unwrap() >= pair.dist);
+            }
+        }
+    }
+
+    #[test]
+    fn test_eval_all_mappers() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 6;
+        let net = gen_network_kind(NetworkKind::Grid2d, g, 2 * g + 1, 1, 2, &mut rng);
+        let landmarks = choose_landmarks(&net, g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let pairs = sample_pairs(&net, 200, &mut rng);
+
+        let evals = eval_all_mappers(&pairs, &coords, &landmarks);
+        assert!(evals.len() == dist_mappers().len());
+        for eval in &evals {
+            assert!(eval.median_rel_error <= eval.p90_rel_error);
+            assert!(eval.p90_rel_error <= eval.max_rel_error);
+            let below_lower_frac = eval.below_lower_frac.unwrap();
+            assert!(below_lower_frac >= 0.0 && below_lower_frac <= 1.0);
+            assert!(eval.spearman <= 1.0001 && eval.spearman >= -1.0001);
+        }
+
+        // approx_max_dist is exactly the lower bound:
+        let max_eval = evals.iter().find(|eval| eval.name == "max").unwrap();
+        assert!(max_eval.below_lower_frac == Some(0.0));
+        assert!(max_eval.above_upper_frac == Some(0.0));
+        assert!(max_eval.spearman > 0.0);
+    }
+
+    #[test]
+    fn test_quantile() {
+        let vals = vec![1.0, 2.0, 3.0, 4.0];
+        assert!(quantile(&vals, 0.5) == 2.0);
+        assert!(quantile(&vals, 0.9) == 4.0);
+        assert!(quantile(&vals, 0.0) == 1.0);
+    }
+}
@@ -0,0 +1,195 @@
+extern crate rand;
+
+use std::hash::Hash;
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+
+use network::{Network};
+use random_util::{choose_k_nums};
+use landmarks::{find_path_landmarks_partial};
+use landmarks::coords::{build_partial_coords, drop_landmark_entries};
+
+/// Results of routing messages between a set of node pairs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoutingStats {
+    pub num_pairs: usize,
+    pub num_success: usize,
+    /// Average length of successful routes. 0 if no route succeeded.
+    pub avg_length: f64,
+}
+
+impl RoutingStats {
+    pub fn success_rate(&self) -> f64 {
+        if self.num_pairs == 0 {
+            return 0.0
+        }
+        (self.num_success as f64) / (self.num_pairs as f64)
+    }
+}
+
+/// Routing quality before and after failure of some of the landmarks.
+#[derive(Debug)]
+pub struct LandmarkFailureReport {
+    /// Positions (Inside the landmarks vector) of the landmarks that have failed.
+    pub failed: Vec<usize>,
+    /// Routing over the original network, before any failure.
+    pub intact: RoutingStats,
+    /// Routing over the network with failed landmarks, using the coordinates computed before
+    /// the failure. Nodes have forgotten the entries of the failed landmarks, but the rest of
+    /// the entries were not updated.
+    pub stale: RoutingStats,
+    /// Routing over the network with failed landmarks, after computing the coordinates again.
+    pub recomputed: RoutingStats,
+}
+
+/// Remove the given nodes from the network, by removing all of their edges.
+/// The nodes stay in the network (isolated), so that node indices remain valid.
+pub fn fail_nodes<Node: Hash + Eq + Clone>(net: &mut Network<Node>, nodes: &Vec<usize>) {
+    for &node in nodes {
+        let neighbors = net.igraph.neighbors(node).collect::<Vec<usize>>();
+        for nei in neighbors {
+            net.igraph.remove_edge(node, nei);
+        }
+    }
+}
+
+/// Route a message between every pair of nodes, using coordinates with optional entries.
+pub fn route_pairs<R: Rng, Node: Hash + Eq + Clone>(pairs: &Vec<(usize, usize)>,
+         amount_close: usize, net: &Network<Node>, pcoords: &Vec<Vec<Option<u64>>>,
+         rng: &mut R) -> RoutingStats {
+
+    let mut num_success: usize = 0;
+    let mut sum_length: u64 = 0;
+    for &(src_node, dst_node) in pairs {
+        if let Some(length) = find_path_landmarks_partial(src_node, dst_node,
+                                        amount_close, net, pcoords, rng) {
+            num_success += 1;
+            sum_length += length;
+        }
+    }
+
+    RoutingStats {
+        num_pairs: pairs.len(),
+        num_success,
+        avg_length: if num_success > 0 {
+            (sum_length as f64) / (num_success as f64)
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Kill a fraction fail_frac of the landmarks after the coordinates have converged,
+/// and measure routing between num_pairs random pairs of surviving nodes: Before the failure,
+/// right after the failure (stale coordinates) and after the coordinates were computed again.
+/// Only pairs that are still connected after the failure are used.
+pub fn simulate_landmark_failures<R: Rng, Node: Hash + Eq + Clone>(net: &Network<Node>,
+         landmarks: &Vec<usize>, fail_frac: f64, amount_close: usize, num_pairs: usize,
+         rng: &mut R) -> LandmarkFailureReport {
+
+    let pcoords = build_partial_coords(net, landmarks);
+    let num_failed = ((landmarks.len() as f64) * fail_frac) as usize;
+    let mut failed = choose_k_nums(num_failed, landmarks.len(), rng)
+        .into_iter().collect::<Vec<usize>>();
+    failed.sort();
+    let failed_nodes = failed.iter().map(|&pos| landmarks[pos]).collect::<Vec<usize>>();
+
+    let mut failed_net = net.clone();
+    fail_nodes(&mut failed_net, &failed_nodes);
+
+    // Choose pairs of surviving nodes that are still connected:
+    let num_nodes = net.igraph.node_count();
+    let node_range: Range<usize> = Range::new(0, num_nodes);
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let mut attempts: usize = 0;
+    while pairs.len() < num_pairs && attempts < 100 * num_pairs {
+        attempts += 1;
+        let src_node = node_range.ind_sample(rng);
+        let dst_node = node_range.ind_sample(rng);
+        if src_node == dst_node || failed_nodes.contains(&src_node)
+            || failed_nodes.contains(&dst_node) {
+            continue;
+        }
+        if failed_net.dist(src_node, dst_node).is_some() {
+            pairs.push((src_node, dst_node));
+        }
+    }
+
+    let intact = route_pairs(&pairs, amount_close, net, &pcoords, rng);
+
+    let mut stale_pcoords = pcoords.clone();
+    drop_landmark_entries(&mut stale_pcoords, &failed);
+    let stale = route_pairs(&pairs, amount_close, &failed_net, &stale_pcoords, rng);
+
+    // Failed landmarks are isolated, hence their entries become None:
+    let recomputed_pcoords = build_partial_coords(&failed_net, landmarks);
+    let recomputed = route_pairs(&pairs, amount_close, &failed_net, &recomputed_pcoords, rng);
+
+    LandmarkFailureReport {
+        failed,
+        intact,
+        stale,
+        recomputed,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network};
+    use landmarks::coords::{build_coords, choose_landmarks, to_partial_coords};
+    use landmarks::coord_mappers::{partial_max_dist};
+
+    #[test]
+    fn test_partial_max_dist() {
+        let a = vec![Some(1), None, Some(5), Some(2)];
+        let b = vec![Some(4), Some(9), None, Some(1)];
+        assert!(partial_max_dist(&a, &b) == Some(3));
+        let c = vec![None, Some(0), None, None];
+        assert!(partial_max_dist(&a, &c) == None);
+        assert!(partial_max_dist(&b, &c) == Some(9));
+    }
+
+    #[test]
+    fn test_fail_nodes() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let mut net = gen_network(0, 6, 13, 1, 2, &mut rng);
+        let num_nodes = net.igraph.node_count();
+        fail_nodes(&mut net, &vec![3, 7]);
+        assert!(net.igraph.node_count() == num_nodes);
+        assert!(net.igraph.neighbors(3).count() == 0);
+        assert!(net.igraph.neighbors(7).count() == 0);
+        assert!(net.dist(3, 7).is_none());
+    }
+
+    #[test]
+    fn test_simulate_landmark_failures() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 7;
+        let net = gen_network(0, g, 2 * g + 1, 1, 2, &mut rng);
+        let landmarks = choose_landmarks(&net, g * g, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        assert!(build_partial_coords(&net, &landmarks) == to_partial_coords(&coords));
+
+        let report = simulate_landmark_failures(&net, &landmarks, 0.5,
+                                                g * g, 50, &mut rng);
+        assert!(report.failed.len() == (g * g) / 2);
+        for stats in &[report.intact, report.stale, report.recomputed] {
+            assert!(stats.num_pairs == 50);
+            assert!(stats.success_rate() >= 0.0 && stats.success_rate() <= 1.0);
+        }
+        // Half of the landmarks are enough to route in a random network:
+        assert!(report.recomputed.success_rate() > 0.5);
+
+        // Nothing fails:
+        let report = simulate_landmark_failures(&net, &landmarks, 0.0,
+                                                g * g, 20, &mut rng);
+        assert!(report.failed.is_empty());
+        assert!(report.intact.success_rate() > 0.5);
+    }
+}
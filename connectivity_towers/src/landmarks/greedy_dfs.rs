@@ -0,0 +1,203 @@
+extern crate ordered_float;
+
+use std::hash::Hash;
+use std::collections::{HashSet};
+use std::mem::size_of;
+
+use self::ordered_float::{OrderedFloat};
+
+use network::{Network};
+use router::{Router, RouteSummary};
+use landmarks::coord_mappers::{approx_max_dist};
+
+/// Result of routing a message using greedy routing with backtracking.
+pub struct DfsRoute {
+    /// Amount of hops the message has traveled, including backtracking hops.
+    pub hops: usize,
+    /// Sum of weights of all edges the message has traveled through.
+    pub weight: u64,
+    /// Amount of hops that were spent on backtracking.
+    pub backtracks: usize,
+    /// Maximum amount of node indices kept inside the message header
+    /// (visited set and backtracking stack).
+    pub max_header_entries: usize,
+}
+
+impl DfsRoute {
+    /// Maximum approximate size in bytes of the message header.
+    pub fn max_header_bytes(&self) -> usize {
+        self.max_header_entries * size_of::<usize>()
+    }
+}
+
+/// Find a path in the network between src_node and dst_node using greedy routing with memory.
+/// The message carries the set of visited nodes and a stack of the nodes it came from.
+/// At every node the message is forwarded to the unvisited neighbour closest to dst_node
+/// (According to approx_max_dist). If all neighbours were visited, the message
+/// goes back to the previous node on the stack.
+///
+/// This is a depth first search, hence delivery is guaranteed if src_node and dst_node
+/// are connected. Returns None only if dst_node is not reachable from src_node.
+pub fn find_path_landmarks_dfs<Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize,
+         net: &Network<Node>, coords: &Vec<Vec<u64>>,
+         landmarks: &Vec<usize>) -> Option<DfsRoute> {
+
+    // Node distance function:
+    let node_dist = |x,y| approx_max_dist(x,y,&coords, &landmarks) as f64;
+    find_path_dfs(src_node, dst_node, net, node_dist)
+}
+
+/// Greedy routing with backtracking over landmarks coordinates, as a Router.
+/// (See find_path_landmarks_dfs)
+pub struct LandmarksDfsRouter<'a> {
+    pub coords: &'a Vec<Vec<u64>>,
+    pub landmarks: &'a Vec<usize>,
+}
+
+impl<'a, Node: Hash + Eq + Clone> Router<Node> for LandmarksDfsRouter<'a> {
+    fn name(&self) -> &str {
+        "landmarks_dfs"
+    }
+
+    fn route(&self, src_node: usize, dst_node: usize, net: &Network<Node>) -> Option<RouteSummary> {
+        find_path_landmarks_dfs(src_node, dst_node, net, self.coords, self.landmarks)
+            .map(|route| RouteSummary {hops: route.hops, weight: route.weight})
+    }
+}
+
+/// Greedy routing with backtracking (See find_path_landmarks_dfs), using any
+/// approximate distance function between nodes (Landmarks coordinates, vivaldi coordinates etc).
+pub fn find_path_dfs<Node: Hash + Eq + Clone, F>(src_node: usize, dst_node: usize,
+         net: &Network<Node>, node_dist: F) -> Option<DfsRoute> 
+    where F: Fn(usize, usize) -> f64 {
+
+    let mut route = DfsRoute {
+        hops: 0,
+        weight: 0,
+        backtracks: 0,
+        max_header_entries: 0,
+    };
+
+    // Message header:
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut stack: Vec<usize> = Vec::new();
+
+    let mut cur_node = src_node;
+    visited.insert(cur_node);
+
+    while cur_node != dst_node {
+        // Closest unvisited neighbour. Ties are broken by node index:
+        let next_node = net.igraph.neighbors(cur_node)
+            .filter(|nei| !visited.contains(nei))
+            .min_by_key(|&nei| (OrderedFloat(node_dist(nei, dst_node)), nei));
+
+        let next_node = match next_node {
+            Some(next_node) => {
+                stack.push(cur_node);
+                visited.insert(next_node);
+                next_node
+            },
+            None => {
+                route.backtracks += 1;
+                match stack.pop() {
+                    Some(prev_node) => prev_node,
+                    // All reachable nodes were visited:
+                    None => return None,
+                }
+            },
+        };
+
+        route.hops += 1;
+        route.weight += *net.igraph.edge_weight(cur_node, next_node).unwrap();
+        route.max_header_entries = route.max_header_entries.max(visited.len() + stack.len());
+        cur_node = next_node;
+    }
+    Some(route)
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network::{random_net};
+    use network_gen::{gen_network};
+    use landmarks::coords::{build_coords, choose_landmarks};
+
+    #[test]
+    fn test_find_path_landmarks_dfs_delivers() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 7;
+        for net_type in 0 .. 5 {
+            let net = gen_network(net_type, g, 2 * g + 1, 1, 2, &mut rng);
+            // Very few landmarks, to make greedy routing get stuck:
+            let landmarks = choose_landmarks(&net, 2, &mut rng);
+            let coords = build_coords(&net, &landmarks).unwrap();
+
+            let num_nodes = net.igraph.node_count();
+            for src_node in (0 .. num_nodes).step_by(7) {
+                for dst_node in (0 .. num_nodes).step_by(5) {
+                    let route = find_path_landmarks_dfs(src_node, dst_node,
+                                            &net, &coords, &landmarks).unwrap();
+                    assert!(route.weight >= net.dist(src_node, dst_node).unwrap());
+                    assert!(route.backtracks <= route.hops);
+                    // Every node is entered at most once, and left backwards at most once:
+                    assert!(route.hops <= 2 * num_nodes);
+                    assert!(route.max_header_entries <= 2 * num_nodes);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_landmarks_dfs_router() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = random_net(64, 5, &mut rng);
+        let landmarks = choose_landmarks(&net, 4, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+        let router = LandmarksDfsRouter {coords: &coords, landmarks: &landmarks};
+
+        for src_node in 0 .. 8 {
+            for dst_node in 30 .. 40 {
+                let route = find_path_landmarks_dfs(src_node, dst_node,
+                                                    &net, &coords, &landmarks).unwrap();
+                assert!(router.route(src_node, dst_node, &net) ==
+                        Some(RouteSummary {hops: route.hops, weight: route.weight}));
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_path_landmarks_dfs_same_node() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = random_net(32, 5, &mut rng);
+        let landmarks = choose_landmarks(&net, 5, &mut rng);
+        let coords = build_coords(&net, &landmarks).unwrap();
+
+        let route = find_path_landmarks_dfs(3, 3, &net, &coords, &landmarks).unwrap();
+        assert!(route.hops == 0);
+        assert!(route.max_header_bytes() == 0);
+    }
+
+    #[test]
+    fn test_find_path_landmarks_dfs_unreachable() {
+        // Two separate components: {0,1} and {2,3}
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 4 {
+            net.add_node(x);
+        }
+        net.igraph.add_edge(0, 1, 1);
+        net.igraph.add_edge(2, 3, 1);
+        let landmarks = vec![0, 2];
+        let coords = vec![vec![0, 9], vec![1, 9], vec![9, 0], vec![9, 1]];
+
+        assert!(find_path_landmarks_dfs(0, 3, &net, &coords, &landmarks).is_none());
+        let route = find_path_landmarks_dfs(0, 1, &net, &coords, &landmarks).unwrap();
+        assert!(route.hops == 1);
+        assert!(route.max_header_entries == 3);
+    }
+}
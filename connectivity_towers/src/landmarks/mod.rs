@@ -1,8 +1,18 @@
 pub mod coords;
 pub mod randomize_coord;
 pub mod coord_mappers;
+pub mod selectors;
+pub mod greedy_dfs;
+pub mod dist_eval;
+pub mod addressing;
+pub mod failures;
+pub mod compact_routing;
+pub mod coord_dht;
+pub mod coord_randomizers;
+pub mod coord_hash;
 
 extern crate rand;
+extern crate ordered_float;
 
 use self::rand::{Rng};
 use self::rand::distributions::{Weighted, WeightedChoice, 
@@ -11,8 +21,13 @@ use self::rand::distributions::{Weighted, WeightedChoice,
 use std::hash::Hash;
 use std::collections::{HashSet, HashMap};
 
+use std::mem::size_of;
+
+use self::ordered_float::{OrderedFloat};
+
 use network::{Network};
-use landmarks::coord_mappers::{approx_max_dist, max_dist};
+use landmarks::coord_mappers::{approx_max_dist, approx_partial_max_dist, max_dist};
+use state_size::{NodeStateSize};
 
 
 /// Try to find a path in the network between src_node and dst_node.
@@ -24,7 +39,35 @@ pub fn find_path_landmarks<R: Rng, Node: Hash + Eq + Clone>(src_node: usize, dst
          mut rng: &mut R) -> Option<u64> {
 
     // Node distance function:
-    let node_dist = |x,y| approx_max_dist(x,y,&coords, &landmarks);
+    let node_dist = |x,y| approx_max_dist(x,y,&coords, &landmarks) as f64;
+    find_path_by_dist(src_node, dst_node, amount_close, net, node_dist, &mut rng)
+}
+
+/// Try to find a path in the network between src_node and dst_node (See find_path_landmarks),
+/// using coordinates with optional entries. Missing entries are ignored. Nodes that have no 
+/// entries in common with dst_node are considered to be infinitely far.
+/// Returns None if path was not found, or Some(path_length)
+pub fn find_path_landmarks_partial<R: Rng, Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize, 
+         amount_close: usize, net: &Network<Node>, 
+         pcoords: &Vec<Vec<Option<u64>>>,
+         mut rng: &mut R) -> Option<u64> {
+
+    // Node distance function:
+    let node_dist = |x,y| match approx_partial_max_dist(x,y,&pcoords) {
+        Some(dist) => dist as f64,
+        None => f64::INFINITY,
+    };
+    find_path_by_dist(src_node, dst_node, amount_close, net, node_dist, &mut rng)
+}
+
+/// Try to find a path in the network between src_node and dst_node (See find_path_landmarks),
+/// using any approximate distance function between nodes.
+/// Returns None if path was not found, or Some(path_length)
+pub fn find_path_by_dist<R: Rng, Node: Hash + Eq + Clone, F>(src_node: usize, dst_node: usize, 
+         amount_close: usize, net: &Network<Node>, node_dist: F,
+         mut rng: &mut R) -> Option<u64> 
+    where F: Fn(usize, usize) -> f64 {
+
     // let calc_weight = |i: usize| {
     //     1 + (((-(node_dist(i, dst_node) as f64)/(0x1000 as f64)).exp())*10000.0) as u32
     // };
@@ -36,7 +79,7 @@ pub fn find_path_landmarks<R: Rng, Node: Hash + Eq + Clone>(src_node: usize, dst
     while cur_node != dst_node {
         let (mut new_cur_node, mut new_dist , _): (usize, u64, _) = 
             net.closest_nodes_structure(cur_node).take(amount_close)
-                .min_by_key(|&(i, _, _)| node_dist(dst_node, i)).unwrap();
+                .min_by_key(|&(i, _, _)| OrderedFloat(node_dist(dst_node, i))).unwrap();
 
         if node_dist(new_cur_node, dst_node) >= node_dist(cur_node, dst_node) {
 
@@ -286,6 +329,16 @@ pub fn gen_areas_rw<R: Rng, Node: Hash + Eq + Clone>(amount_close: usize,
     areas
 }
 
+/// Size of the area every node keeps (As generated by one of the gen_areas functions).
+pub fn areas_state_sizes(areas: &Vec<Vec<KnownNode>>) -> Vec<NodeStateSize> {
+    areas.iter()
+        .map(|area_nodes| NodeStateSize {
+            entries: area_nodes.len(),
+            bytes: area_nodes.len() * size_of::<KnownNode>(),
+        })
+        .collect()
+}
+
 /// Try to find a path in the network between src_node and dst_node.
 /// Returns None if path was not found, or Some(path_length)
 pub fn find_path_landmarks_areas<R: Rng, Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize, 
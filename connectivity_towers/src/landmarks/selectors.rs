@@ -0,0 +1,421 @@
+extern crate rand;
+extern crate petgraph;
+
+use std::cmp::{min, Reverse};
+use std::collections::{BinaryHeap};
+use std::hash::{Hash};
+use std::u64;
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+use self::petgraph::algo::{dijkstra};
+use self::petgraph::visit::{EdgeRef};
+
+use network::{Network};
+use chord::{RingKey};
+use random_util::{choose_k_nums};
+use hash_util::{sha256_hash};
+use landmarks::coords::{choose_landmarks};
+
+
+/// A strategy for choosing landmarks in a network.
+pub trait LandmarkSelector<Node> {
+    /// Short name of the strategy, used for printing experiments results.
+    fn name(&self) -> &str;
+
+    /// Choose landmarks (Indices of nodes). num_landmarks is the wanted amount of landmarks.
+    /// Some strategies only approximate this amount.
+    /// The returned landmarks are sorted, for determinism.
+    fn select(&self, net: &Network<Node>, num_landmarks: usize, rng: &mut dyn Rng) -> Vec<usize>;
+}
+
+/// Choose landmarks uniformly at random.
+pub struct RandomSelector;
+
+/// Choose the nodes with the highest degree.
+pub struct HighestDegreeSelector;
+
+/// Greedy k-center: Every new landmark is the node farthest from all previously chosen
+/// landmarks. The first landmark is chosen randomly.
+pub struct FarthestPointSelector;
+
+/// Choose the nodes with the highest approximate betweenness centrality.
+/// Betweenness is approximated by accumulating shortest paths from num_sources random sources.
+pub struct BetweennessSelector {
+    pub num_sources: usize,
+}
+
+/// Distributed self selection: A node is a landmark if hash(salt, node) is below a threshold.
+/// The threshold is set so that the expected amount of landmarks is num_landmarks,
+/// the actual amount of landmarks may vary.
+pub struct HashSelector {
+    pub salt: u64,
+}
+
+
+impl<Node: Hash + Eq + Clone> LandmarkSelector<Node> for RandomSelector {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn select(&self, net: &Network<Node>, num_landmarks: usize, mut rng: &mut dyn Rng) -> Vec<usize> {
+        choose_landmarks(net, num_landmarks, &mut rng)
+    }
+}
+
+impl<Node: Hash + Eq + Clone> LandmarkSelector<Node> for HighestDegreeSelector {
+    fn name(&self) -> &str {
+        "degree"
+    }
+
+    fn select(&self, net: &Network<Node>, num_landmarks: usize, _rng: &mut dyn Rng) -> Vec<usize> {
+        let mut nodes = (0 .. net.igraph.node_count()).collect::<Vec<usize>>();
+        // Highest degree first. Ties are broken by node index:
+        nodes.sort_by_key(|&x_i| (Reverse(net.igraph.neighbors(x_i).count()), x_i));
+        nodes.truncate(num_landmarks);
+        nodes.sort();
+        nodes
+    }
+}
+
+impl<Node: Hash + Eq + Clone> LandmarkSelector<Node> for FarthestPointSelector {
+    fn name(&self) -> &str {
+        "farthest"
+    }
+
+    fn select(&self, net: &Network<Node>, num_landmarks: usize, mut rng: &mut dyn Rng) -> Vec<usize> {
+        let num_nodes = net.igraph.node_count();
+        let num_landmarks = min(num_landmarks, num_nodes);
+        let mut landmarks: Vec<usize> = Vec::new();
+        if num_landmarks == 0 {
+            return landmarks;
+        }
+
+        // Distance from every node to the closest chosen landmark:
+        let mut min_dists: Vec<u64> = vec![u64::MAX; num_nodes];
+        let node_range: Range<usize> = Range::new(0, num_nodes);
+        let mut next_landmark = node_range.ind_sample(&mut rng);
+
+        loop {
+            landmarks.push(next_landmark);
+            if landmarks.len() >= num_landmarks {
+                break;
+            }
+
+            let scores = dijkstra(&net.igraph, next_landmark, None, |e| *e.weight());
+            for (&x_i, &dist) in &scores {
+                min_dists[x_i] = min(min_dists[x_i], dist);
+            }
+            // Farthest node. Ties are broken by node index:
+            next_landmark = (0 .. num_nodes)
+                .max_by_key(|&x_i| (min_dists[x_i], Reverse(x_i)))
+                .unwrap();
+        }
+
+        landmarks.sort();
+        landmarks
+    }
+}
+
+/// Accumulate the betweenness of all nodes over shortest paths from source.
+/// (Brandes algorithm, for one source)
+fn accumulate_betweenness<Node>(net: &Network<Node>, source: usize,
+                                betweenness: &mut Vec<f64>) {
+
+    let num_nodes = net.igraph.node_count();
+    let mut dists: Vec<Option<u64>> = vec![None; num_nodes];
+    // Amount of shortest paths from source to every node:
+    let mut num_paths: Vec<f64> = vec![0.0; num_nodes];
+    let mut preds: Vec<Vec<usize>> = vec![Vec::new(); num_nodes];
+    // Nodes in order of non decreasing distance from source:
+    let mut order: Vec<usize> = Vec::new();
+    let mut done: Vec<bool> = vec![false; num_nodes];
+
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = BinaryHeap::new();
+    dists[source] = Some(0);
+    num_paths[source] = 1.0;
+    heap.push(Reverse((0, source)));
+
+    while let Some(Reverse((dist, x_i))) = heap.pop() {
+        if done[x_i] {
+            continue;
+        }
+        done[x_i] = true;
+        order.push(x_i);
+
+        for (_, nei_i, &weight) in net.igraph.edges(x_i) {
+            let new_dist = dist + weight;
+            match dists[nei_i] {
+                Some(nei_dist) if nei_dist < new_dist => {},
+                Some(nei_dist) if nei_dist == new_dist => {
+                    num_paths[nei_i] += num_paths[x_i];
+                    preds[nei_i].push(x_i);
+                },
+                _ => {
+                    dists[nei_i] = Some(new_dist);
+                    num_paths[nei_i] = num_paths[x_i];
+                    preds[nei_i] = vec![x_i];
+                    heap.push(Reverse((new_dist, nei_i)));
+                },
+            };
+        }
+    }
+
+    // Accumulate dependencies, from the farthest node back to the source:
+    let mut dependency: Vec<f64> = vec![0.0; num_nodes];
+    for &x_i in order.iter().rev() {
+        for &pred_i in &preds[x_i] {
+            dependency[pred_i] += (num_paths[pred_i] / num_paths[x_i]) * (1.0 + dependency[x_i]);
+        }
+        if x_i != source {
+            betweenness[x_i] += dependency[x_i];
+        }
+    }
+}
+
+/// Approximate betweenness centrality of all nodes, using shortest paths from the given sources.
+pub fn approx_betweenness<Node>(net: &Network<Node>, sources: &Vec<usize>) -> Vec<f64> {
+    let mut betweenness: Vec<f64> = vec![0.0; net.igraph.node_count()];
+    for &source in sources {
+        accumulate_betweenness(net, source, &mut betweenness);
+    }
+    betweenness
+}
+
+impl<Node: Hash + Eq + Clone> LandmarkSelector<Node> for BetweennessSelector {
+    fn name(&self) -> &str {
+        "betweenness"
+    }
+
+    fn select(&self, net: &Network<Node>, num_landmarks: usize, mut rng: &mut dyn Rng) -> Vec<usize> {
+        let num_nodes = net.igraph.node_count();
+        let num_sources = min(self.num_sources, num_nodes);
+        let mut sources = choose_k_nums(num_sources, num_nodes, &mut rng)
+            .into_iter().collect::<Vec<usize>>();
+        // Sort the sources for determinism:
+        sources.sort();
+
+        let betweenness = approx_betweenness(net, &sources);
+        let mut nodes = (0 .. num_nodes).collect::<Vec<usize>>();
+        // Highest betweenness first. Ties are broken by node index:
+        nodes.sort_by(|&a, &b| betweenness[b].partial_cmp(&betweenness[a]).unwrap()
+                      .then(a.cmp(&b)));
+        nodes.truncate(num_landmarks);
+        nodes.sort();
+        nodes
+    }
+}
+
+impl<Node: Hash + Eq + Clone> LandmarkSelector<Node> for HashSelector {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn select(&self, net: &Network<Node>, num_landmarks: usize, _rng: &mut dyn Rng) -> Vec<usize> {
+        let num_nodes = net.igraph.node_count();
+        if num_nodes == 0 {
+            return Vec::new();
+        }
+        if num_landmarks >= num_nodes {
+            return (0 .. num_nodes).collect::<Vec<usize>>();
+        }
+        let threshold = (u64::MAX / (num_nodes as u64)).saturating_mul(num_landmarks as u64);
+
+        // Every node could check this condition locally:
+        (0 .. num_nodes)
+            .filter(|&x_i| sha256_hash(self.salt, net.index_to_node(x_i).unwrap()) < threshold)
+            .collect::<Vec<usize>>()
+    }
+}
+
+/// All landmark selection strategies (With default parameters).
+pub fn landmark_selectors() -> Vec<Box<dyn LandmarkSelector<RingKey>>> {
+    vec![
+        Box::new(RandomSelector),
+        Box::new(HighestDegreeSelector),
+        Box::new(FarthestPointSelector),
+        Box::new(BetweennessSelector {num_sources: 32}),
+        Box::new(HashSelector {salt: 0x1337}),
+    ]
+}
+
+/// Choose a landmark selection strategy (With default parameters) by name.
+/// Returns None if there is no strategy with the given name.
+pub fn landmark_selector_by_name(name: &str) -> Option<Box<dyn LandmarkSelector<RingKey>>> {
+    landmark_selectors().into_iter()
+        .find(|selector| selector.name() == name)
+}
+
+/// Choose the landmark selection strategy of an experiment from its command line arguments:
+/// The strategy is given as --selector=<name> (Default: random).
+/// Returns the strategy and the rest of the arguments.
+pub fn landmark_selector_from_args<I>(args: I) -> (Box<dyn LandmarkSelector<RingKey>>, Vec<String>)
+    where I: Iterator<Item=String> {
+
+    let mut selector: Box<dyn LandmarkSelector<RingKey>> = Box::new(RandomSelector);
+    let mut rest_args = Vec::new();
+    for arg in args {
+        if arg.starts_with("--selector=") {
+            let name = &arg["--selector=".len() ..];
+            selector = landmark_selector_by_name(name)
+                .unwrap_or_else(|| panic!("Unknown landmark selector: {}", name));
+        } else {
+            rest_args.push(arg);
+        }
+    }
+    (selector, rest_args)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network::{random_net};
+    use network_gen::{gen_network};
+    use landmarks::coords::{build_coords};
+
+    fn all_selectors() -> Vec<Box<dyn LandmarkSelector<usize>>> {
+        vec![
+            Box::new(RandomSelector),
+            Box::new(HighestDegreeSelector),
+            Box::new(FarthestPointSelector),
+            Box::new(BetweennessSelector {num_sources: 20}),
+            Box::new(HashSelector {salt: 7}),
+        ]
+    }
+
+    #[test]
+    fn test_selectors_basic() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = random_net(200, 4, &mut rng);
+        let num_landmarks = 20;
+
+        for selector in all_selectors() {
+            let landmarks = selector.select(&net, num_landmarks, &mut rng);
+            assert!(landmarks.len() > 0, "{}", selector.name());
+            // Sorted and unique:
+            assert!(landmarks.windows(2).all(|w| w[0] < w[1]), "{}", selector.name());
+            assert!(landmarks.iter().all(|&x_i| x_i < net.igraph.node_count()));
+            assert!(build_coords(&net, &landmarks).is_some());
+            if selector.name() != "hash" {
+                assert!(landmarks.len() == num_landmarks, "{}", selector.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_highest_degree_selector() {
+        // A star with a tail: 0 is connected to 1,2,3,4 and 4 is connected to 5.
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 6 {
+            net.add_node(x);
+        }
+        for x in 1 .. 5 {
+            net.igraph.add_edge(0, x, 1);
+        }
+        net.igraph.add_edge(4, 5, 1);
+
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        assert!(HighestDegreeSelector.select(&net, 2, &mut rng) == vec![0, 4]);
+    }
+
+    #[test]
+    fn test_farthest_point_selector_path() {
+        // A path network 0 - 1 - ... - 9
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 10 {
+            net.add_node(x);
+        }
+        for x in 0 .. 9 {
+            net.igraph.add_edge(x, x + 1, 1);
+        }
+
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        // Both ends of the path must be chosen, no matter where we started:
+        let landmarks = FarthestPointSelector.select(&net, 3, &mut rng);
+        assert!(landmarks.contains(&0));
+        assert!(landmarks.contains(&9));
+    }
+
+    #[test]
+    fn test_approx_betweenness_path() {
+        // A path network 0 - 1 - 2 - 3 - 4
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 5 {
+            net.add_node(x);
+        }
+        for x in 0 .. 4 {
+            net.igraph.add_edge(x, x + 1, 1);
+        }
+        // Using all sources gives the exact betweenness (Every pair counted twice):
+        let betweenness = approx_betweenness(&net, &(0 .. 5).collect());
+        let expected = vec![0.0, 6.0, 8.0, 6.0, 0.0];
+        for x in 0 .. 5 {
+            assert!((betweenness[x] - expected[x]).abs() < 0.0001);
+        }
+
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let selector = BetweennessSelector {num_sources: 5};
+        assert!(selector.select(&net, 1, &mut rng) == vec![2]);
+    }
+
+    #[test]
+    fn test_hash_selector_deterministic() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 10;
+        let net = gen_network(0, g, 2 * g + 1, 1, 2, &mut rng);
+        let num_landmarks = 100;
+
+        let selector = HashSelector {salt: 1};
+        let landmarks1 = selector.select(&net, num_landmarks, &mut rng);
+        let landmarks2 = selector.select(&net, num_landmarks, &mut rng);
+        assert!(landmarks1 == landmarks2);
+        // Amount of landmarks should be close to the expected amount:
+        assert!(landmarks1.len() > num_landmarks / 2);
+        assert!(landmarks1.len() < num_landmarks * 2);
+
+        let other_landmarks = HashSelector {salt: 2}.select(&net, num_landmarks, &mut rng);
+        assert!(landmarks1 != other_landmarks);
+    }
+
+    #[test]
+    fn test_landmark_selector_by_name() {
+        for selector in landmark_selectors() {
+            let found = landmark_selector_by_name(selector.name()).unwrap();
+            assert!(found.name() == selector.name());
+        }
+        assert!(landmark_selector_by_name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_landmark_selector_from_args() {
+        let args = vec!["a", "--selector=hash", "b"].into_iter().map(String::from);
+        let (selector, rest_args) = landmark_selector_from_args(args);
+        assert!(selector.name() == "hash");
+        assert!(rest_args == vec!["a".to_string(), "b".to_string()]);
+
+        let (selector, rest_args) = landmark_selector_from_args(Vec::new().into_iter());
+        assert!(selector.name() == "random");
+        assert!(rest_args.is_empty());
+    }
+
+    #[test]
+    fn test_hash_selector_small_nets() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let selector = HashSelector {salt: 1};
+
+        let empty_net: Network<usize> = Network::new();
+        assert!(selector.select(&empty_net, 5, &mut rng).is_empty());
+
+        // More landmarks than nodes:
+        let net = random_net(10, 3, &mut rng);
+        assert!(selector.select(&net, 20, &mut rng) == (0 .. 10).collect::<Vec<usize>>());
+    }
+}
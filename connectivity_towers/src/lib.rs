@@ -2,8 +2,14 @@ pub mod network;
 pub mod network_gen;
 pub mod landmarks;
 pub mod random_util;
+pub mod hash_util;
 pub mod smallest_k;
 pub mod statistic;
 pub mod index_pair;
 pub mod chord;
+pub mod kademlia;
 pub mod towers;
+pub mod convergence;
+pub mod router;
+pub mod state_size;
+pub mod vivaldi;
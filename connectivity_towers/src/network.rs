@@ -9,6 +9,7 @@ use self::rand::distributions::{IndependentSample, Range};
 use self::petgraph::algo::{dijkstra, connected_components};
 use self::petgraph::visit::{EdgeRef};
 
+#[derive(Clone)]
 pub struct Network<Node> {
     pub igraph: petgraph::graphmap::GraphMap<usize,u64,petgraph::Undirected>,
     nodes_index: HashMap<Node, usize>, // Node -> Index
@@ -243,7 +243,9 @@ pub fn random_weighted_net_planar<R: Rng>(num_nodes: usize, num_cons: usize,
     let planar_dist = |i: usize, j:usize| {
         let (a,b) = index_to_coord.get(&i).unwrap().clone();
         let (c,d) = index_to_coord.get(&j).unwrap().clone();
-        (c - a).pow(2) + (d - b).pow(2)
+        let dx = if c > a { c - a } else { a - c };
+        let dy = if d > b { d - b } else { b - d };
+        dx.pow(2) + dy.pow(2)
     };
 
     for u in 0 .. num_nodes {
@@ -322,6 +324,46 @@ pub fn random_tree<R: Rng>(num_nodes: usize,
     net
 }
 
+/// Kinds of networks generated by gen_network.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub enum NetworkKind {
+    Rand,
+    Grid2d,
+    RandGrid2d,
+    Planar,
+    Tree,
+}
+
+impl NetworkKind {
+    /// All kinds of networks, ordered by net_type.
+    pub fn all() -> Vec<NetworkKind> {
+        vec![NetworkKind::Rand, NetworkKind::Grid2d, NetworkKind::RandGrid2d,
+             NetworkKind::Planar, NetworkKind::Tree]
+    }
+
+    /// The net_type argument of gen_network for this kind of network.
+    pub fn net_type(&self) -> usize {
+        match *self {
+            NetworkKind::Rand => 0,
+            NetworkKind::Grid2d => 1,
+            NetworkKind::RandGrid2d => 2,
+            NetworkKind::Planar => 3,
+            NetworkKind::Tree => 4,
+        }
+    }
+
+    /// Short name, used for printing experiments results.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            NetworkKind::Rand => "rand",
+            NetworkKind::Grid2d => "2d",
+            NetworkKind::RandGrid2d => "rand+2d",
+            NetworkKind::Planar => "planar",
+            NetworkKind::Tree => "tree",
+        }
+    }
+}
+
 /// Generate a network according to given type.
 /// g -- amount of nodes (logarithmic).
 /// l -- maximum key space for chord based networks (logarithmic)
@@ -369,6 +411,12 @@ pub fn gen_network<R:Rng>(net_type: usize, g: usize,l: usize,
     }
 }
 
+/// Generate a network of the given kind. See gen_network.
+pub fn gen_network_kind<R:Rng>(kind: NetworkKind, g: usize,l: usize, 
+        min_weighted_len: u64, max_weighted_len: u64, rng: &mut R) -> Network<RingKey> {
+    gen_network(kind.net_type(), g, l, min_weighted_len, max_weighted_len, rng)
+}
+
 
 #[cfg(test)]
 mod tests {
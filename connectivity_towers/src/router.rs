@@ -0,0 +1,22 @@
+use network::{Network};
+
+/// Summary of the route a message has traveled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouteSummary {
+    /// Amount of hops the message has traveled.
+    pub hops: usize,
+    /// Sum of weights of all edges the message has traveled through.
+    pub weight: u64,
+}
+
+/// A deterministic routing scheme. All the state used for routing (Routing tables,
+/// coordinates, overlay etc.) is prepared in advance, and routing a message
+/// between two nodes always gives the same route.
+pub trait Router<Node> {
+    /// Short name of the scheme, used for printing experiments results.
+    fn name(&self) -> &str;
+
+    /// Route a message from src_node to dst_node.
+    /// Returns None if the message could not be delivered.
+    fn route(&self, src_node: usize, dst_node: usize, net: &Network<Node>) -> Option<RouteSummary>;
+}
@@ -0,0 +1,103 @@
+/// Size of the routing state a single node keeps.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct NodeStateSize {
+    /// Amount of entries (fingers, known nodes, coordinate entries etc).
+    pub entries: usize,
+    /// Approximate amount of bytes used by the entries.
+    pub bytes: usize,
+}
+
+impl NodeStateSize {
+    pub fn add(&self, other: &NodeStateSize) -> NodeStateSize {
+        NodeStateSize {
+            entries: self.entries + other.entries,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+}
+
+/// Routing state that could be measured.
+pub trait StateSize {
+    fn state_size(&self) -> NodeStateSize;
+}
+
+/// Distribution of a value across all nodes.
+#[derive(Clone, Debug)]
+pub struct DistStats {
+    pub mean: f64,
+    pub max: usize,
+    pub p99: usize,
+}
+
+/// Distribution of state sizes across all nodes.
+#[derive(Clone, Debug)]
+pub struct StateSizeStats {
+    pub entries: DistStats,
+    pub bytes: DistStats,
+}
+
+/// Calculate mean, max and 99th percentile of the given values.
+pub fn dist_stats(vals: &Vec<usize>) -> DistStats {
+    assert!(vals.len() > 0, "No values, aborting!");
+    let mut sorted_vals = vals.clone();
+    sorted_vals.sort();
+
+    // Nearest rank percentile:
+    let p99_rank = ((sorted_vals.len() as f64) * 0.99).ceil() as usize;
+
+    DistStats {
+        mean: (sorted_vals.iter().sum::<usize>() as f64) / (sorted_vals.len() as f64),
+        max: sorted_vals[sorted_vals.len() - 1],
+        p99: sorted_vals[p99_rank - 1],
+    }
+}
+
+/// Calculate state size statistics over all nodes.
+pub fn state_size_stats(sizes: &Vec<NodeStateSize>) -> StateSizeStats {
+    StateSizeStats {
+        entries: dist_stats(&sizes.iter().map(|size| size.entries).collect()),
+        bytes: dist_stats(&sizes.iter().map(|size| size.bytes).collect()),
+    }
+}
+
+/// Get the state size of every node.
+pub fn nodes_state_sizes<S: StateSize>(states: &Vec<S>) -> Vec<NodeStateSize> {
+    states.iter()
+        .map(|state| state.state_size())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dist_stats() {
+        let vals = (1 .. 201).collect::<Vec<usize>>();
+        let stats = dist_stats(&vals);
+        assert!((stats.mean - 100.5).abs() < 0.0001);
+        assert!(stats.max == 200);
+        assert!(stats.p99 == 198);
+    }
+
+    #[test]
+    fn test_dist_stats_single() {
+        let stats = dist_stats(&vec![7]);
+        assert!((stats.mean - 7.0).abs() < 0.0001);
+        assert!(stats.max == 7);
+        assert!(stats.p99 == 7);
+    }
+
+    #[test]
+    fn test_state_size_stats() {
+        let sizes = vec![
+            NodeStateSize {entries: 1, bytes: 8},
+            NodeStateSize {entries: 3, bytes: 24},
+        ];
+        let stats = state_size_stats(&sizes);
+        assert!((stats.entries.mean - 2.0).abs() < 0.0001);
+        assert!(stats.bytes.max == 24);
+        assert!(sizes[0].add(&sizes[1]) == NodeStateSize {entries: 4, bytes: 32});
+    }
+}
@@ -0,0 +1,385 @@
+extern crate petgraph;
+
+use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+
+use self::petgraph::graphmap;
+
+use network::{Network};
+use towers::{LocalTowerInfo, TowersDist};
+
+/// Information of some node about one of its k closest towers of some color.
+#[derive(Clone)]
+pub struct KTowerInfo {
+    /// Shortest path to the tower.
+    pub info: LocalTowerInfo,
+    /// Second best path to the tower, through a different gateway: (gateway, distance).
+    /// The path continues along the gateways of the alternative gateway, which does not use
+    /// the node itself as its gateway to the tower. None if no such neighbour exists.
+    pub alt: Option<(usize, u64)>,
+}
+
+impl KTowerInfo {
+    fn key(&self) -> (u64, usize, usize, Option<(usize, u64)>) {
+        (self.info.distance, self.info.gateway, self.info.tower_node, self.alt)
+    }
+}
+
+/// Pick the k closest distinct towers of a node out of a list of candidates.
+/// Every candidate is a path offered by a neighbour, together with the gateway the neighbour
+/// itself uses to reach the tower.
+/// For every tower the best (distance, gateway) candidate is the main path, and the best
+/// candidate through another gateway, not bouncing back to the node, is the alternative path.
+/// A tower has no alternative path to itself.
+/// Towers are ordered by (distance, tower_node). The result is sorted by the same order.
+fn select_k_closest(node: usize, candidates: Vec<(LocalTowerInfo, usize)>, 
+                    k: usize) -> Vec<KTowerInfo> {
+    let mut best: HashMap<usize, LocalTowerInfo> = HashMap::new();
+    for &(ref candidate, _) in &candidates {
+        let is_better = match best.get(&candidate.tower_node) {
+            None => true,
+            Some(cur_info) => (candidate.distance, candidate.gateway) <
+                (cur_info.distance, cur_info.gateway),
+        };
+        if is_better {
+            best.insert(candidate.tower_node, candidate.clone());
+        }
+    }
+    let mut infos = best.into_iter()
+        .map(|(_, info)| info)
+        .collect::<Vec<LocalTowerInfo>>();
+    infos.sort_by_key(|info| (info.distance, info.tower_node));
+    infos.truncate(k);
+
+    infos.into_iter()
+        .map(|info| {
+            if info.tower_node == node {
+                return KTowerInfo {info, alt: None};
+            }
+            let alt = candidates.iter()
+                .filter(|&&(ref candidate, nei_gateway)| 
+                        candidate.tower_node == info.tower_node &&
+                        candidate.gateway != info.gateway &&
+                        nei_gateway != node)
+                .map(|&(ref candidate, _)| (candidate.distance, candidate.gateway))
+                .min()
+                .map(|(distance, gateway)| (gateway, distance));
+            KTowerInfo {info, alt}
+        })
+        .collect()
+}
+
+/// Perform one iteration of calculating k closest towers info.
+/// All nodes recalculate their lists from the lists of their neighbors in the previous
+/// iteration.
+/// Returns (nodes_changed, entries_changed) for this iteration.
+fn iter_towers_info_k<Node: Hash + Eq + Clone>(net: &Network<Node>,
+                 chosen_towers: &Vec<Vec<usize>>, k: usize, towers_dist: TowersDist,
+                 towers_info_k: &mut Vec<Vec<Vec<KTowerInfo>>>) -> (usize, usize) {
+
+    let mut nodes_changed = 0;
+    let mut entries_changed = 0;
+    let mut new_towers_info_k = Vec::new();
+
+    for node in 0 .. net.igraph.node_count() {
+        let mut node_changed = false;
+        let mut node_info = Vec::new();
+        for tower_color in 0 .. chosen_towers.len() {
+            let mut candidates: Vec<(LocalTowerInfo, usize)> = Vec::new();
+            if chosen_towers[tower_color].contains(&node) {
+                candidates.push((LocalTowerInfo {
+                    gateway: node,
+                    distance: 0,
+                    tower_node: node,
+                }, node));
+            }
+            for nei in net.igraph.neighbors(node) {
+                let edge_len = towers_dist.edge_len(net, node, nei);
+                for nei_entry in &towers_info_k[nei][tower_color] {
+                    candidates.push((LocalTowerInfo {
+                        gateway: nei,
+                        distance: nei_entry.info.distance + edge_len,
+                        tower_node: nei_entry.info.tower_node,
+                    }, nei_entry.info.gateway));
+                }
+            }
+            let entries = select_k_closest(node, candidates, k);
+
+            let old_entries = &towers_info_k[node][tower_color];
+            let num_changed = (0 .. entries.len().max(old_entries.len()))
+                .filter(|&i| match (entries.get(i), old_entries.get(i)) {
+                    (Some(a), Some(b)) => a.key() != b.key(),
+                    _ => true,
+                })
+                .count();
+            if num_changed > 0 {
+                node_changed = true;
+                entries_changed += num_changed;
+            }
+            node_info.push(entries);
+        }
+        if node_changed {
+            nodes_changed += 1;
+        }
+        new_towers_info_k.push(node_info);
+    }
+
+    *towers_info_k = new_towers_info_k;
+    (nodes_changed, entries_changed)
+}
+
+/// Converge information about the k closest towers of every color.
+/// Every node will learn about the k closest distinct towers of every color,
+/// ordered by (distance, tower_node). For every tower the gateway is the first hop of a
+/// shortest path to the tower, ties broken by gateway.
+///
+/// If a tower T is one of the k closest towers of a node, T is also one of the k closest towers
+/// of every node along the shortest path from the node to T. Therefore following the gateways
+/// always leads to T.
+///
+/// Every entry also keeps a second best gateway to the tower, if there is one (See KTowerInfo).
+pub fn calc_towers_info_k<Node: Hash + Eq + Clone>(net: &Network<Node>,
+    chosen_towers: &Vec<Vec<usize>>, k: usize, towers_dist: TowersDist)
+    -> Vec<Vec<Vec<KTowerInfo>>> {
+
+    let mut towers_info_k = vec![vec![Vec::new(); chosen_towers.len()];
+                                 net.igraph.node_count()];
+
+    loop {
+        let (_, entries_changed) =
+            iter_towers_info_k(net, chosen_towers, k, towers_dist, &mut towers_info_k);
+        if entries_changed == 0 {
+            break;
+        }
+    }
+    towers_info_k
+}
+
+/// Convert towers info of a single local tower per color to k closest towers info.
+/// The resulting entries have no alternative gateways.
+pub fn to_towers_info_k(towers_info: &Vec<Vec<Option<LocalTowerInfo>>>)
+    -> Vec<Vec<Vec<KTowerInfo>>> {
+
+    towers_info.iter()
+        .map(|node_info| node_info.iter()
+             .map(|info| info.iter()
+                  .map(|info| KTowerInfo {info: info.clone(), alt: None})
+                  .collect())
+             .collect())
+        .collect()
+}
+
+/// Forget about failed towers.
+/// Nodes drop entries of failed towers, without recalculating their lists.
+/// Failed towers still forward messages as regular nodes.
+pub fn drop_failed_towers(towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>,
+                          failed_towers: &HashSet<usize>) -> Vec<Vec<Vec<KTowerInfo>>> {
+    towers_info_k.iter()
+        .map(|node_info| node_info.iter()
+             .map(|entries| entries.iter()
+                  .filter(|entry| !failed_towers.contains(&entry.info.tower_node))
+                  .cloned()
+                  .collect())
+             .collect())
+        .collect()
+}
+
+/// Build the overlay directed graph of the towers, using all the k closest towers.
+/// A tower T is connected to a tower T' if T' is one of the k closest towers of T of some color.
+/// Every edge is labeled by (distance, tower_color). Failed towers are not part of the overlay.
+pub fn towers_overlay_k(chosen_towers: &Vec<Vec<usize>>,
+        towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>, failed_towers: &HashSet<usize>)
+    -> graphmap::DiGraphMap<usize,(u64, usize)> {
+
+    let mut towers_graph: graphmap::DiGraphMap<usize,(u64, usize)> =
+        graphmap::DiGraphMap::new();
+
+    for tower_color in 0 .. chosen_towers.len() {
+        for &tower_node in &chosen_towers[tower_color] {
+            if !failed_towers.contains(&tower_node) {
+                towers_graph.add_node(tower_node);
+            }
+        }
+    }
+
+    let graph_nodes = towers_graph.nodes().collect::<Vec<usize>>();
+    for tower_node in graph_nodes {
+        for tower_color in 0 .. chosen_towers.len() {
+            for entry in &towers_info_k[tower_node][tower_color] {
+                let info = &entry.info;
+                if failed_towers.contains(&info.tower_node) {
+                    continue;
+                }
+                let label = (info.distance, tower_color);
+                let is_better = match towers_graph.edge_weight(tower_node, info.tower_node) {
+                    None => true,
+                    Some(&old_label) => label < old_label,
+                };
+                if is_better {
+                    towers_graph.add_edge(tower_node, info.tower_node, label);
+                }
+            }
+        }
+    }
+    towers_graph
+}
+
+/// Fraction of entries that are not towers themselves and have an alternative gateway.
+pub fn alt_gateway_frac(towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>) -> f64 {
+    let mut num_alt = 0;
+    let mut num_entries = 0;
+    for node in 0 .. towers_info_k.len() {
+        for entries in &towers_info_k[node] {
+            for entry in entries.iter().filter(|entry| entry.info.tower_node != node) {
+                num_entries += 1;
+                if entry.alt.is_some() {
+                    num_alt += 1;
+                }
+            }
+        }
+    }
+    (num_alt as f64) / (num_entries as f64)
+}
+
+/// Average amount of distinct gateways over the entries of every (node, color), including
+/// alternative gateways. Entries that point to the node itself are not counted.
+pub fn avg_distinct_gateways(towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>) -> f64 {
+    let mut sum_gateways = 0;
+    let mut num_lists = 0;
+    for node in 0 .. towers_info_k.len() {
+        for entries in &towers_info_k[node] {
+            let gateways = entries.iter()
+                .flat_map(|entry| Some(entry.info.gateway).into_iter()
+                          .chain(entry.alt.map(|(gateway, _)| gateway)))
+                .filter(|&gateway| gateway != node)
+                .collect::<HashSet<usize>>();
+            sum_gateways += gateways.len();
+            num_lists += 1;
+        }
+    }
+    (sum_gateways as f64) / (num_lists as f64)
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::gen_network;
+    use towers::{choose_towers, calc_towers_info, towers_overlay, overlay_connectivity};
+
+    #[test]
+    fn test_calc_towers_info_k() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let k = 3;
+        for net_type in 0 .. 5 {
+            let net = gen_network(net_type, 7, 15, 1, 10, &mut rng);
+            let chosen_towers = choose_towers(&net, 5, 4, &mut rng);
+            let towers_info_k = calc_towers_info_k(&net, &chosen_towers, k,
+                                                   TowersDist::Weighted);
+
+            for tower_color in 0 .. chosen_towers.len() {
+                let tower_dists = chosen_towers[tower_color].iter()
+                    .map(|&tower_node| (tower_node, (0 .. net.igraph.node_count())
+                         .map(|node| net.dist(tower_node, node).unwrap())
+                         .collect::<Vec<u64>>()))
+                    .collect::<Vec<(usize, Vec<u64>)>>();
+
+                for node in 0 .. net.igraph.node_count() {
+                    // The k closest towers, according to Dijkstra:
+                    let mut expected = tower_dists.iter()
+                        .map(|&(tower_node, ref dists)| (dists[node], tower_node))
+                        .collect::<Vec<(u64, usize)>>();
+                    expected.sort();
+                    expected.truncate(k);
+
+                    let entries = &towers_info_k[node][tower_color];
+                    let found = entries.iter()
+                        .map(|entry| (entry.info.distance, entry.info.tower_node))
+                        .collect::<Vec<(u64, usize)>>();
+                    assert!(found == expected);
+
+                    // Following the gateways leads to the tower:
+                    for entry in entries {
+                        let info = &entry.info;
+                        if info.gateway == node {
+                            assert!(info.tower_node == node);
+                            assert!(entry.alt.is_none());
+                            continue;
+                        }
+                        let edge_len = *net.igraph.edge_weight(node, info.gateway).unwrap();
+                        let gateway_entry = towers_info_k[info.gateway][tower_color].iter()
+                            .find(|gateway_entry| gateway_entry.info.tower_node == info.tower_node)
+                            .unwrap();
+                        assert!(gateway_entry.info.distance + edge_len == info.distance);
+
+                        // The alternative gateway is the best other neighbour that knows the
+                        // tower, and does not go back through the node:
+                        let expected_alt = net.igraph.neighbors(node)
+                            .filter(|&nei| nei != info.gateway)
+                            .filter_map(|nei| towers_info_k[nei][tower_color].iter()
+                                 .find(|nei_entry| nei_entry.info.tower_node == info.tower_node &&
+                                       nei_entry.info.gateway != node)
+                                 .map(|nei_entry| (nei_entry.info.distance + 
+                                           *net.igraph.edge_weight(node, nei).unwrap(), nei)))
+                            .min()
+                            .map(|(distance, nei)| (nei, distance));
+                        assert!(entry.alt == expected_alt);
+                        if let Some((_, alt_distance)) = entry.alt {
+                            assert!(alt_distance >= info.distance);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_towers_overlay_k() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 2, &mut rng);
+        let chosen_towers = choose_towers(&net, 2, 16, &mut rng);
+        let no_failures = HashSet::new();
+
+        // A single closest tower gives the usual overlay:
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+        let towers_info_1 = calc_towers_info_k(&net, &chosen_towers, 1, TowersDist::Hops);
+        for node in 0 .. net.igraph.node_count() {
+            for tower_color in 0 .. chosen_towers.len() {
+                assert!(towers_info_1[node][tower_color].len() == 1);
+                assert!(towers_info_1[node][tower_color][0].info.distance ==
+                        towers_info[node][tower_color].clone().unwrap().distance);
+            }
+        }
+        let overlay = towers_overlay(&chosen_towers, &towers_info);
+        let overlay_1 = towers_overlay_k(&chosen_towers,
+                                         &to_towers_info_k(&towers_info), &no_failures);
+        assert!(overlay.all_edges().collect::<Vec<_>>() ==
+                overlay_1.all_edges().collect::<Vec<_>>());
+
+        // More towers give more overlay links:
+        let towers_info_2 = calc_towers_info_k(&net, &chosen_towers, 2, TowersDist::Hops);
+        let overlay_2 = towers_overlay_k(&chosen_towers, &towers_info_2, &no_failures);
+        assert!(overlay_2.edge_count() > overlay.edge_count());
+        assert!(overlay_connectivity(&overlay_2) == (true, true));
+        assert!(avg_distinct_gateways(&towers_info_2) > avg_distinct_gateways(&towers_info_1));
+        // Most entries have a second gateway:
+        assert!(alt_gateway_frac(&towers_info_1) > 0.5);
+        assert!(alt_gateway_frac(&to_towers_info_k(&towers_info)) == 0.0);
+
+        // Failed towers are removed from the overlay:
+        let failed_tower = chosen_towers[0][0];
+        let failed_towers = [failed_tower].iter().cloned().collect::<HashSet<usize>>();
+        let towers_info_failed = drop_failed_towers(&towers_info_2, &failed_towers);
+        let overlay_failed = towers_overlay_k(&chosen_towers, &towers_info_failed,
+                                              &failed_towers);
+        assert!(!overlay_failed.contains_node(failed_tower));
+        assert!(towers_info_failed.iter()
+                .all(|node_info| node_info.iter()
+                     .all(|entries| entries.iter()
+                          .all(|entry| entry.info.tower_node != failed_tower))));
+    }
+}
@@ -0,0 +1,712 @@
+extern crate rand;
+extern crate petgraph;
+
+pub mod routing;
+pub mod k_closest;
+pub mod overlay_report;
+pub mod placement;
+
+use self::rand::{Rng};
+use self::petgraph::graphmap;
+use self::petgraph::algo::{kosaraju_scc, connected_components};
+
+use network::{Network};
+use std::hash::Hash;
+
+use random_util::{choose_k_nums};
+use convergence::{ConvergenceObserver, NullObserver, IterStats};
+use state_size::{NodeStateSize};
+use std::collections::{VecDeque, HashSet};
+use std::time::Instant;
+use std::mem::size_of;
+
+
+/// Information of some node in the network about 
+/// a local tower (Closest of a certain color).
+#[derive(Clone)]
+pub struct LocalTowerInfo {
+    gateway: usize,
+    distance: u64,
+    tower_node: usize,
+}
+
+impl LocalTowerInfo {
+    /// The next node on the path to the tower. A tower is its own gateway.
+    pub fn gateway(&self) -> usize {
+        self.gateway
+    }
+
+    /// Distance to the tower.
+    pub fn distance(&self) -> u64 {
+        self.distance
+    }
+
+    /// The local tower this information is about.
+    pub fn tower_node(&self) -> usize {
+        self.tower_node
+    }
+}
+
+/// How the distance to a local tower is measured.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TowersDist {
+    /// Every edge is of length 1.
+    Hops,
+    /// Use the weights of the edges. All weights must be positive.
+    Weighted,
+}
+
+impl TowersDist {
+    /// Length of the edge between node and nei.
+    fn edge_len<Node: Hash + Eq + Clone>(&self, net: &Network<Node>, 
+                                         node: usize, nei: usize) -> u64 {
+        match *self {
+            TowersDist::Hops => 1,
+            TowersDist::Weighted => {
+                let weight = *net.igraph.edge_weight(node, nei).unwrap();
+                // A tower could otherwise adopt another tower at distance 0:
+                assert!(weight > 0, "Zero weight edges are not supported");
+                weight
+            }
+        }
+    }
+}
+
+/// Choose nodes to be towers. We pick num_towers towers of every color. There are num_colors
+/// different tower colors.
+pub fn choose_towers<Node: Hash + Eq + Clone, R: Rng>(net: &Network<Node>, 
+                  num_towers: usize, num_colors: usize, rng: &mut R) -> Vec<Vec<usize>> {
+
+    let mut chosen_towers: Vec<Vec<usize>> = Vec::new();
+
+    for _ in 0 .. num_colors {
+        // Pick random towers for a certain color:
+        let mut ctowers = choose_k_nums(num_towers, net.igraph.node_count(), rng)
+            .into_iter()
+            .collect::<Vec<usize>>();
+        // Sort for determinism:
+        ctowers.sort();
+        chosen_towers.push(ctowers);
+    }
+    chosen_towers
+}
+
+/// Update operation: A given node is told about a path to a local tower.
+struct UpdateOper {
+    node: usize,
+    tower_color: usize,
+    tower_index: usize,
+    local_tower_info: LocalTowerInfo,
+}
+
+fn init_towers_info(num_nodes: usize, num_colors: usize) ->
+    Vec<Vec<Option<LocalTowerInfo>>> {
+    let mut towers_info: Vec<Vec<Option<LocalTowerInfo>>> = Vec::new();
+    for i in 0 .. num_nodes {
+        towers_info.push(Vec::new());
+        for _ in 0 .. num_colors {
+            towers_info[i].push(None);
+        }
+    }
+    towers_info
+}
+
+/// Key for comparing offers of local tower info. Smaller is better.
+/// Ties are broken by tower_node before gateway: A gateway then only switches to a better
+/// tower, which is also better for the nodes behind it, so gateway chains stay consistent.
+fn info_key(info: &LocalTowerInfo) -> (u64, usize, usize) {
+    (info.distance, info.tower_node, info.gateway)
+}
+
+/// Converge information about local towers. 
+/// Every node will learn about the closest local towers
+/// of every color.
+/// This function uses a lot of memory, and can not be run
+/// for networks of size 2^16.
+#[allow(dead_code)]
+pub fn calc_towers_info_mem_heavy<Node: Hash + Eq + Clone>(net: &Network<Node>, 
+    chosen_towers: &Vec<Vec<usize>>) -> Vec<Vec<Option<LocalTowerInfo>>> {
+    calc_towers_info_mem_heavy_dist(net, chosen_towers, TowersDist::Hops)
+}
+
+/// Converge information about local towers, measuring distance according to towers_dist.
+/// See calc_towers_info_mem_heavy.
+pub fn calc_towers_info_mem_heavy_dist<Node: Hash + Eq + Clone>(net: &Network<Node>, 
+    chosen_towers: &Vec<Vec<usize>>, towers_dist: TowersDist) 
+    -> Vec<Vec<Option<LocalTowerInfo>>> {
+
+    let mut towers_info = init_towers_info(net.igraph.node_count(), 
+                                           chosen_towers.len());
+
+    let mut pending_opers: VecDeque<UpdateOper> = VecDeque::new();
+
+    // Add initial update operations from all chosen towers.
+    // Later the information about those towers will propagage all over the network.
+    for tower_color in 0 .. chosen_towers.len() {
+        for tower_index in 0 .. chosen_towers[tower_color].len() {
+            let tower_node = chosen_towers[tower_color][tower_index];
+            pending_opers.push_back(UpdateOper {
+                node: tower_node,
+                tower_color,
+                tower_index,
+                local_tower_info: LocalTowerInfo {
+                    gateway: tower_node,
+                    distance: 0,
+                    tower_node,
+                }
+            });
+        }
+    }
+
+    // Start handling pending operations:
+    while let Some(oper) = pending_opers.pop_front() {
+        let ltower_info_opt: &mut Option<LocalTowerInfo> = 
+            &mut towers_info[oper.node][oper.tower_color];
+
+        let should_update = match *ltower_info_opt {
+            None => true, 
+            Some(ref ltower_info) => {
+                // Check if the new offered tower info (oper.local_tower_info) 
+                // is better than the current one (ltower_info):
+                info_key(ltower_info) > info_key(&oper.local_tower_info)
+            }
+        };
+
+        if !should_update {
+            continue
+        }
+
+        // Update local tower information:
+        *ltower_info_opt = Some(oper.local_tower_info.clone());
+        // Notify all neighbors about new information:
+        for nei in net.igraph.neighbors(oper.node) {
+            pending_opers.push_back(UpdateOper {
+                node: nei,
+                tower_color: oper.tower_color,
+                tower_index: oper.tower_index,
+                local_tower_info: LocalTowerInfo {
+                    gateway: oper.node,
+                    distance: oper.local_tower_info.distance + 
+                        towers_dist.edge_len(net, oper.node, nei),
+                    tower_node: oper.local_tower_info.tower_node,
+                }
+            });
+        }
+    }
+
+    towers_info
+}
+
+
+/// Perform one iteration of calculating towers info.
+/// Returns (nodes_changed, entries_changed) for this iteration.
+fn iter_towers_info<Node: Hash + Eq + Clone>(net: &Network<Node>,
+                 chosen_towers: &Vec<Vec<usize>>, towers_dist: TowersDist,
+                 towers_info: &mut Vec<Vec<Option<LocalTowerInfo>>>) -> (usize, usize) {
+
+    let mut changed_nodes: HashSet<usize> = HashSet::new();
+    let mut entries_changed = 0;
+
+    for node in net.igraph.nodes() {
+        for nei in net.igraph.neighbors(node) {
+            let edge_len = towers_dist.edge_len(net, node, nei);
+            for tower_color in 0 .. chosen_towers.len() {
+                if towers_info[node][tower_color].is_none() {
+                    continue
+                }
+                // This is the candidate LocalTowerInfo for nei:
+                let mut candidate_info = towers_info[node][tower_color].clone().unwrap();
+                candidate_info.distance += edge_len;
+                candidate_info.gateway = node;
+                // Current nei's LocalTowerInfo:
+
+                if towers_info[nei][tower_color].is_none() {
+                    changed_nodes.insert(nei);
+                    entries_changed += 1;
+                    towers_info[nei][tower_color] = Some(candidate_info);
+                    continue
+                }
+
+                let nei_info = towers_info[nei][tower_color].clone().unwrap();
+
+                if info_key(&candidate_info) < info_key(&nei_info) {
+
+                    changed_nodes.insert(nei);
+                    entries_changed += 1;
+                    towers_info[nei][tower_color] = Some(candidate_info);
+
+                }
+            }
+        }
+    }
+    (changed_nodes.len(), entries_changed)
+}
+
+
+/// Converge information about local towers. 
+/// Every node will learn about the closest local towers
+/// of every color.
+pub fn calc_towers_info<Node: Hash + Eq + Clone>(net: &Network<Node>, 
+    chosen_towers: &Vec<Vec<usize>>) -> Vec<Vec<Option<LocalTowerInfo>>> {
+    calc_towers_info_observed(net, chosen_towers, &mut NullObserver)
+}
+
+/// Converge information about local towers, using the weights of the edges.
+/// Every node will learn about the closest local towers (By weighted distance)
+/// of every color.
+pub fn calc_towers_info_weighted<Node: Hash + Eq + Clone>(net: &Network<Node>, 
+    chosen_towers: &Vec<Vec<usize>>) -> Vec<Vec<Option<LocalTowerInfo>>> {
+    calc_towers_info_dist_observed(net, chosen_towers, TowersDist::Weighted, &mut NullObserver)
+}
+
+/// Converge information about local towers. 
+/// observer is notified after every iteration.
+pub fn calc_towers_info_observed<Node: Hash + Eq + Clone, O: ConvergenceObserver>(
+    net: &Network<Node>, chosen_towers: &Vec<Vec<usize>>, 
+    observer: &mut O) -> Vec<Vec<Option<LocalTowerInfo>>> {
+    calc_towers_info_dist_observed(net, chosen_towers, TowersDist::Hops, observer)
+}
+
+/// Converge information about local towers, measuring distance according to towers_dist.
+/// Ties between local towers are broken by (distance, tower_node, gateway).
+/// observer is notified after every iteration.
+pub fn calc_towers_info_dist_observed<Node: Hash + Eq + Clone, O: ConvergenceObserver>(
+    net: &Network<Node>, chosen_towers: &Vec<Vec<usize>>, towers_dist: TowersDist,
+    observer: &mut O) -> Vec<Vec<Option<LocalTowerInfo>>> {
+
+    let mut towers_info = init_towers_info(net.igraph.node_count(), 
+                                           chosen_towers.len());
+
+    // Add initial update operations from all chosen towers.
+    // Later the information about those towers will propagage all over the network.
+    for tower_color in 0 .. chosen_towers.len() {
+        for tower_index in 0 .. chosen_towers[tower_color].len() {
+            let tower_node = chosen_towers[tower_color][tower_index];
+            let tower_info = &mut towers_info[tower_node][tower_color];
+            *tower_info = Some(LocalTowerInfo {
+                gateway: tower_node,
+                distance: 0,
+                tower_node,
+            });
+        }
+    }
+
+    let mut iter = 0;
+    loop {
+        let start = Instant::now();
+        let (nodes_changed, entries_changed) = 
+            iter_towers_info(net, chosen_towers, towers_dist, &mut towers_info);
+        observer.on_iter(&IterStats {
+            iter,
+            nodes_changed,
+            entries_changed,
+            skipped_updates: 0,
+            elapsed: start.elapsed(),
+        });
+        if entries_changed == 0 {
+            break;
+        }
+        iter += 1;
+    }
+
+    towers_info
+}
+
+/// Make sure that all LocalTowerInfo fields are not None
+pub fn is_towers_info_filled(towers_info: &Vec<Vec<Option<LocalTowerInfo>>>) -> bool {
+    for node in 0 .. towers_info.len() {
+        for tower_color in 0 .. towers_info[node].len() {
+            if towers_info[node][tower_color].is_none() {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+/// The local tower of a node of the given color.
+/// Returns None if the node does not know any tower of this color.
+pub fn tower_for(towers_info: &Vec<Vec<Option<LocalTowerInfo>>>, 
+                 node: usize, tower_color: usize) -> Option<usize> {
+    towers_info[node][tower_color].as_ref().map(|info| info.tower_node)
+}
+
+/// Path from a node to its local tower of the given color, following the gateways.
+/// The path includes both the node and the tower.
+/// Returns None if some node along the way does not know a tower of this color, or if the
+/// gateways form a loop.
+pub fn path_to_tower(towers_info: &Vec<Vec<Option<LocalTowerInfo>>>, 
+                     node: usize, tower_color: usize) -> Option<Vec<usize>> {
+
+    let mut path = vec![node];
+    let mut cur_node = node;
+    loop {
+        let info = towers_info[cur_node][tower_color].as_ref()?;
+        if info.gateway == cur_node {
+            return Some(path);
+        }
+        cur_node = info.gateway;
+        path.push(cur_node);
+        // A simple path can not be longer than the amount of nodes:
+        if path.len() > towers_info.len() {
+            return None
+        }
+    }
+}
+
+/// Find all entries (node, tower_color) with an invalid gateways chain.
+/// A chain is valid if it is loop free, every gateway is a neighbour of the previous node,
+/// it ends at the tower of the entry, and its length (Measured according to towers_dist) 
+/// equals the distance of the entry.
+/// Entries that are None are not checked.
+pub fn invalid_gateway_chains<Node: Hash + Eq + Clone>(net: &Network<Node>,
+        towers_info: &Vec<Vec<Option<LocalTowerInfo>>>, 
+        towers_dist: TowersDist) -> Vec<(usize, usize)> {
+
+    let mut invalid = Vec::new();
+    for node in 0 .. towers_info.len() {
+        for tower_color in 0 .. towers_info[node].len() {
+            let info = match towers_info[node][tower_color] {
+                Some(ref info) => info,
+                None => continue,
+            };
+            let is_valid = match path_to_tower(towers_info, node, tower_color) {
+                None => false,
+                Some(path) => {
+                    let is_adjacent = (1 .. path.len())
+                        .all(|i| net.igraph.contains_edge(path[i-1], path[i]));
+                    is_adjacent && {
+                        let length = (1 .. path.len())
+                            .map(|i| towers_dist.edge_len(net, path[i-1], path[i]))
+                            .sum::<u64>();
+                        *path.last().unwrap() == info.tower_node && length == info.distance
+                    }
+                },
+            };
+            if !is_valid {
+                invalid.push((node, tower_color));
+            }
+        }
+    }
+    invalid
+}
+
+/// Size of the local towers info every node keeps.
+/// Only filled entries are counted.
+pub fn towers_state_sizes(towers_info: &Vec<Vec<Option<LocalTowerInfo>>>) -> Vec<NodeStateSize> {
+    towers_info.iter()
+        .map(|node_info| {
+            let entries = node_info.iter()
+                .filter(|info| info.is_some())
+                .count();
+            NodeStateSize {
+                entries,
+                bytes: entries * size_of::<LocalTowerInfo>(),
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct TowerGraphNode {
+    tower_color: usize,
+    tower_index: usize,
+}
+
+/// Build the overlay directed graph of the towers in the network
+/// and the connections between them.
+/// A tower T is connected to a tower T' if T' is the closest tower to T of some color.
+/// Every edge is labeled by (distance, tower_color). If T' is the closest tower to T of a few
+/// colors, the label with the smallest (distance, tower_color) is kept.
+pub fn towers_overlay(chosen_towers: &Vec<Vec<usize>>, 
+        towers_info: &Vec<Vec<Option<LocalTowerInfo>>>) -> graphmap::DiGraphMap<usize,(u64, usize)> {
+
+    let mut towers_graph: graphmap::DiGraphMap<usize,(u64, usize)> = 
+        graphmap::DiGraphMap::new();
+
+    // Add towers as nodes to the graph:
+    for tower_color in 0 .. chosen_towers.len() {
+        for tower_index in 0 .. chosen_towers[tower_color].len() {
+            towers_graph.add_node(chosen_towers[tower_color][tower_index]);
+        }
+    }
+
+    // For every tower, add all connections to closest local towers as nodes.
+    let graph_nodes = towers_graph.nodes().collect::<Vec<usize>>();
+    for tower_node in graph_nodes {
+        for tower_color in 0 .. chosen_towers.len() {
+            let info = towers_info[tower_node][tower_color].clone().unwrap();
+            let label = (info.distance, tower_color);
+            let is_better = match towers_graph.edge_weight(tower_node, info.tower_node) {
+                None => true,
+                Some(&old_label) => label < old_label,
+            };
+            if is_better {
+                towers_graph.add_edge(tower_node, info.tower_node, label);
+            }
+        }
+    }
+    towers_graph
+}
+
+/// Check if overlay directed graph of towers is connected.
+/// Returns (connected, strongly_connected)
+pub fn is_connected(chosen_towers: &Vec<Vec<usize>>, 
+        towers_info: &Vec<Vec<Option<LocalTowerInfo>>>) -> (bool, bool) {
+
+    overlay_connectivity(&towers_overlay(chosen_towers, towers_info))
+}
+
+/// Check if an overlay directed graph of towers is connected.
+/// Returns (connected, strongly_connected)
+pub fn overlay_connectivity(towers_graph: &graphmap::DiGraphMap<usize,(u64, usize)>) 
+    -> (bool, bool) {
+
+    let sconnected_comps = kosaraju_scc(towers_graph);
+    (connected_components(towers_graph) == 1, sconnected_comps.len() == 1)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use network_gen::gen_network;
+    use convergence::{TraceRecorder};
+    use self::rand::{StdRng};
+
+    #[test]
+    fn test_calc_towers_info() {
+        // Generate a random network:
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 2, &mut rng);
+
+        let chosen_towers = choose_towers(&net, 4, 16, &mut rng);
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+        assert!(is_connected(&chosen_towers, &towers_info) == (true, true));
+        assert!(is_towers_info_filled(&towers_info));
+
+    }
+
+    #[test]
+    fn test_towers_state_sizes() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 2, &mut rng);
+
+        let chosen_towers = choose_towers(&net, 4, 16, &mut rng);
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+
+        // Every node keeps exactly one entry for every color:
+        let sizes = towers_state_sizes(&towers_info);
+        assert!(sizes.len() == net.igraph.node_count());
+        assert!(sizes.iter().all(|size| size.entries == 16));
+    }
+
+    #[test]
+    fn test_path_to_tower() {
+        // A path network 0 - 1 - 2 - 3 - 4, with a tower at 0:
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 5 {
+            net.add_node(x);
+        }
+        for x in 0 .. 4 {
+            net.igraph.add_edge(x, x + 1, 3);
+        }
+        let chosen_towers = vec![vec![0]];
+        let mut towers_info = calc_towers_info(&net, &chosen_towers);
+
+        assert!(tower_for(&towers_info, 4, 0) == Some(0));
+        assert!(path_to_tower(&towers_info, 4, 0) == Some(vec![4, 3, 2, 1, 0]));
+        assert!(path_to_tower(&towers_info, 0, 0) == Some(vec![0]));
+        let info = towers_info[4][0].clone().unwrap();
+        assert!((info.gateway(), info.distance(), info.tower_node()) == (3, 4, 0));
+
+        assert!(invalid_gateway_chains(&net, &towers_info, TowersDist::Hops).is_empty());
+        // Distances were measured in hops:
+        assert!(invalid_gateway_chains(&net, &towers_info, TowersDist::Weighted).len() == 4);
+        let towers_info_weighted = calc_towers_info_weighted(&net, &chosen_towers);
+        assert!(invalid_gateway_chains(&net, &towers_info_weighted, 
+                                       TowersDist::Weighted).is_empty());
+
+        // Create a gateways loop between 1 and 2:
+        towers_info[1][0] = Some(LocalTowerInfo {gateway: 2, distance: 1, tower_node: 0});
+        assert!(path_to_tower(&towers_info, 4, 0) == None);
+        let invalid = invalid_gateway_chains(&net, &towers_info, TowersDist::Hops);
+        assert!(invalid == vec![(1, 0), (2, 0), (3, 0), (4, 0)]);
+
+        towers_info[2][0] = None;
+        assert!(tower_for(&towers_info, 2, 0) == None);
+        assert!(path_to_tower(&towers_info, 3, 0) == None);
+    }
+
+    #[test]
+    fn test_invalid_gateway_chains_non_adjacent() {
+        // A path network 0 - 1 - 2 - 3 - 4, with a tower at 0:
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 5 {
+            net.add_node(x);
+        }
+        for x in 0 .. 4 {
+            net.igraph.add_edge(x, x + 1, 3);
+        }
+        let chosen_towers = vec![vec![0]];
+
+        // 4 uses 2 as a gateway, although they are not neighbours. 
+        // The distances match the lengths of the chain 4 - 2 - 1 - 0:
+        let mut towers_info = calc_towers_info(&net, &chosen_towers);
+        towers_info[4][0] = Some(LocalTowerInfo {gateway: 2, distance: 3, tower_node: 0});
+        assert!(invalid_gateway_chains(&net, &towers_info, TowersDist::Hops) == vec![(4, 0)]);
+
+        let mut towers_info_weighted = calc_towers_info_weighted(&net, &chosen_towers);
+        towers_info_weighted[4][0] = Some(LocalTowerInfo {gateway: 2, distance: 9, tower_node: 0});
+        assert!(invalid_gateway_chains(&net, &towers_info_weighted, 
+                                       TowersDist::Weighted) == vec![(4, 0)]);
+    }
+
+    #[test]
+    fn test_towers_info_gateways_consistent() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        for net_type in 0 .. 5 {
+            let net = gen_network(net_type, 8, 17, 1, 2, &mut rng);
+            let chosen_towers = choose_towers(&net, 2, 16, &mut rng);
+            for towers_info in &[calc_towers_info(&net, &chosen_towers),
+                                 calc_towers_info_mem_heavy(&net, &chosen_towers)] {
+                // Every node agrees with its gateway about the tower:
+                for node in 0 .. net.igraph.node_count() {
+                    for tower_color in 0 .. chosen_towers.len() {
+                        let info = towers_info[node][tower_color].clone().unwrap();
+                        let gateway_info = towers_info[info.gateway][tower_color].clone().unwrap();
+                        assert!(gateway_info.tower_node == info.tower_node);
+                        if info.gateway != node {
+                            assert!(gateway_info.distance + 1 == info.distance);
+                        }
+                    }
+                }
+                assert!(invalid_gateway_chains(&net, towers_info, TowersDist::Hops).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_calc_towers_info_weighted() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        for net_type in 0 .. 5 {
+            let net = gen_network(net_type, 7, 15, 1, 10, &mut rng);
+            let chosen_towers = choose_towers(&net, 3, 8, &mut rng);
+            let towers_info = calc_towers_info_weighted(&net, &chosen_towers);
+            let towers_info_mem_heavy = 
+                calc_towers_info_mem_heavy_dist(&net, &chosen_towers, TowersDist::Weighted);
+            assert!(is_towers_info_filled(&towers_info));
+
+            for tower_color in 0 .. chosen_towers.len() {
+                // Dijkstra from every tower of this color:
+                let tower_dists = chosen_towers[tower_color].iter()
+                    .map(|&tower_node| (0 .. net.igraph.node_count())
+                         .map(|node| net.dist(tower_node, node).unwrap())
+                         .collect::<Vec<u64>>())
+                    .collect::<Vec<Vec<u64>>>();
+
+                for node in 0 .. net.igraph.node_count() {
+                    let info = towers_info[node][tower_color].clone().unwrap();
+                    let min_dist = tower_dists.iter().map(|dists| dists[node]).min().unwrap();
+                    assert!(info.distance == min_dist);
+                    assert!(net.dist(info.tower_node, node).unwrap() == info.distance);
+
+                    // The gateway is on a shortest path to the same tower:
+                    let gateway_info = towers_info[info.gateway][tower_color].clone().unwrap();
+                    assert!(gateway_info.tower_node == info.tower_node);
+                    if info.gateway != node {
+                        let edge_len = *net.igraph.edge_weight(node, info.gateway).unwrap();
+                        assert!(gateway_info.distance + edge_len == info.distance);
+                    }
+
+                    // Tie breaking is deterministic, so both methods agree:
+                    let other_info = towers_info_mem_heavy[node][tower_color].clone().unwrap();
+                    assert!((info.distance, info.gateway, info.tower_node) ==
+                            (other_info.distance, other_info.gateway, other_info.tower_node));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_towers_dist_hops() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 10, &mut rng);
+        let chosen_towers = choose_towers(&net, 3, 8, &mut rng);
+
+        // Hops mode ignores the weights of the edges:
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+        let mut net_hops = net.clone();
+        for (a, b, _) in net.igraph.all_edges() {
+            net_hops.igraph.add_edge(a, b, 1);
+        }
+        let towers_info_hops = calc_towers_info_weighted(&net_hops, &chosen_towers);
+        for node in 0 .. net.igraph.node_count() {
+            for tower_color in 0 .. chosen_towers.len() {
+                let info = towers_info[node][tower_color].clone().unwrap();
+                let info_hops = towers_info_hops[node][tower_color].clone().unwrap();
+                assert!((info.distance, info.gateway, info.tower_node) ==
+                        (info_hops.distance, info_hops.gateway, info_hops.tower_node));
+            }
+        }
+    }
+
+
+    #[test]
+    #[should_panic(expected = "Zero weight edges are not supported")]
+    fn test_towers_dist_zero_weight() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let mut net = gen_network(0, 6, 13, 1, 10, &mut rng);
+        let chosen_towers = choose_towers(&net, 2, 4, &mut rng);
+        let (a, b) = net.igraph.all_edges().map(|(a, b, _)| (a, b)).next().unwrap();
+        net.igraph.add_edge(a, b, 0);
+        calc_towers_info_weighted(&net, &chosen_towers);
+    }
+
+    /// (distance, gateway, tower_node) of every entry.
+    fn info_keys(towers_info: &Vec<Vec<Option<LocalTowerInfo>>>) 
+        -> Vec<Vec<Option<(u64, usize, usize)>>> {
+        towers_info.iter()
+            .map(|node_info| node_info.iter()
+                 .map(|info| info.as_ref().map(|info| 
+                           (info.distance, info.gateway, info.tower_node)))
+                 .collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_towers_info_fixpoint() {
+        for net_type in 0 .. 5 {
+            for &towers_dist in &[TowersDist::Hops, TowersDist::Weighted] {
+                let seed: &[_] = &[1,2,3,4,5];
+                let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+                // Short edges and many towers, to get many ties between towers:
+                let net = gen_network(net_type, 8, 17, 1, 3, &mut rng);
+                let chosen_towers = choose_towers(&net, 16, 8, &mut rng);
+
+                let mut recorder = TraceRecorder::new();
+                let mut towers_info = calc_towers_info_dist_observed(&net, &chosen_towers, 
+                                                                     towers_dist, &mut recorder);
+                assert!(recorder.trace.len() <= net.igraph.node_count());
+
+                // A fixpoint was reached: Another iteration changes nothing.
+                assert!(iter_towers_info(&net, &chosen_towers, towers_dist, 
+                                         &mut towers_info) == (0, 0));
+                assert!(invalid_gateway_chains(&net, &towers_info, towers_dist).is_empty());
+
+                // The fixpoint is unique, so the queue based calculation reaches the same one:
+                let towers_info_queue = 
+                    calc_towers_info_mem_heavy_dist(&net, &chosen_towers, towers_dist);
+                assert!(info_keys(&towers_info_queue) == info_keys(&towers_info));
+            }
+        }
+    }
+}
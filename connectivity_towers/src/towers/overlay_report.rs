@@ -0,0 +1,236 @@
+extern crate petgraph;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use self::petgraph::graphmap;
+use self::petgraph::algo::{kosaraju_scc, connected_components};
+
+use state_size::{DistStats, dist_stats};
+
+/// Structure of the towers overlay graph.
+#[derive(Clone, Debug)]
+pub struct OverlayReport {
+    pub num_towers: usize,
+    pub connected: bool,
+    pub strongly_connected: bool,
+    /// Amount of strongly connected components.
+    pub num_sccs: usize,
+    /// Distribution of the sizes of the strongly connected components.
+    pub scc_sizes: DistStats,
+    /// Distribution of the amount of other towers linking to a tower.
+    pub in_degree: DistStats,
+    /// Distribution of the amount of other towers a tower links to.
+    pub out_degree: DistStats,
+    /// Maximum amount of overlay hops between two towers.
+    /// None if the overlay is not strongly connected.
+    pub diameter: Option<usize>,
+    /// Amount of strongly connected components along the longest path in the
+    /// condensation DAG. 1 if the overlay is strongly connected.
+    pub condensation_depth: usize,
+    /// Towers that have no links from other towers, or no links to other towers.
+    /// A tower that is alone in the overlay is not isolated.
+    pub isolated_towers: Vec<usize>,
+    /// Colors that have no towers inside the largest strongly connected component.
+    pub isolated_colors: Vec<usize>,
+}
+
+/// Amount of overlay hops from a tower to all the towers it can reach.
+fn overlay_hops_from(tower: usize, overlay: &graphmap::DiGraphMap<usize,(u64, usize)>)
+    -> HashMap<usize, usize> {
+
+    let mut hops: HashMap<usize, usize> = HashMap::new();
+    let mut pending: VecDeque<usize> = VecDeque::new();
+    hops.insert(tower, 0);
+    pending.push_back(tower);
+    while let Some(cur_tower) = pending.pop_front() {
+        let cur_hops = hops[&cur_tower];
+        for next_tower in overlay.neighbors(cur_tower) {
+            if !hops.contains_key(&next_tower) {
+                hops.insert(next_tower, cur_hops + 1);
+                pending.push_back(next_tower);
+            }
+        }
+    }
+    hops
+}
+
+/// Amount of strongly connected components along the longest path of the condensation DAG.
+fn condensation_depth(sccs: &Vec<Vec<usize>>,
+                      overlay: &graphmap::DiGraphMap<usize,(u64, usize)>) -> usize {
+
+    let mut tower_scc: HashMap<usize, usize> = HashMap::new();
+    for (scc_index, scc) in sccs.iter().enumerate() {
+        for &tower in scc {
+            tower_scc.insert(tower, scc_index);
+        }
+    }
+
+    // Edges of the condensation DAG:
+    let mut dag_edges: Vec<HashSet<usize>> = vec![HashSet::new(); sccs.len()];
+    let mut in_degree: Vec<usize> = vec![0; sccs.len()];
+    for (a, b, _) in overlay.all_edges() {
+        let (scc_a, scc_b) = (tower_scc[&a], tower_scc[&b]);
+        if scc_a != scc_b && dag_edges[scc_a].insert(scc_b) {
+            in_degree[scc_b] += 1;
+        }
+    }
+
+    // Longest path, visiting the DAG in topological order:
+    let mut depth: Vec<usize> = vec![1; sccs.len()];
+    let mut pending: VecDeque<usize> = (0 .. sccs.len())
+        .filter(|&scc_index| in_degree[scc_index] == 0)
+        .collect();
+    while let Some(scc_index) = pending.pop_front() {
+        for &next_index in &dag_edges[scc_index] {
+            depth[next_index] = depth[next_index].max(depth[scc_index] + 1);
+            in_degree[next_index] -= 1;
+            if in_degree[next_index] == 0 {
+                pending.push_back(next_index);
+            }
+        }
+    }
+    depth.into_iter().max().unwrap_or(0)
+}
+
+/// Summarize the structure of a towers overlay graph (See towers_overlay and
+/// towers_overlay_k).
+/// Self loops (A tower being its own closest tower of its color) are not counted as links.
+pub fn overlay_report(chosen_towers: &Vec<Vec<usize>>,
+                      overlay: &graphmap::DiGraphMap<usize,(u64, usize)>) -> OverlayReport {
+
+    let mut towers = overlay.nodes().collect::<Vec<usize>>();
+    // Sort for determinism:
+    towers.sort();
+
+    let mut sccs = kosaraju_scc(overlay);
+    for scc in sccs.iter_mut() {
+        scc.sort();
+    }
+
+    let out_degrees = towers.iter()
+        .map(|&tower| overlay.neighbors(tower).filter(|&nei| nei != tower).count())
+        .collect::<Vec<usize>>();
+    let mut in_degrees: HashMap<usize, usize> = HashMap::new();
+    for (a, b, _) in overlay.all_edges() {
+        if a != b {
+            *in_degrees.entry(b).or_insert(0) += 1;
+        }
+    }
+    let in_degrees = towers.iter()
+        .map(|tower| *in_degrees.get(tower).unwrap_or(&0))
+        .collect::<Vec<usize>>();
+
+    let strongly_connected = sccs.len() == 1;
+    let diameter = if strongly_connected {
+        towers.iter()
+            .map(|&tower| overlay_hops_from(tower, overlay).values().cloned().max().unwrap())
+            .max()
+    } else {
+        None
+    };
+
+    let isolated_towers = (0 .. towers.len())
+        .filter(|&i| towers.len() > 1 && (in_degrees[i] == 0 || out_degrees[i] == 0))
+        .map(|i| towers[i])
+        .collect::<Vec<usize>>();
+
+    // Largest strongly connected component. Ties are broken by the smallest tower:
+    let largest_scc = sccs.iter()
+        .max_by_key(|scc| (scc.len(), -(scc[0] as isize)))
+        .map(|scc| scc.iter().cloned().collect::<HashSet<usize>>())
+        .unwrap_or_default();
+    let isolated_colors = (0 .. chosen_towers.len())
+        .filter(|&tower_color| !chosen_towers[tower_color].iter()
+                .any(|tower| largest_scc.contains(tower)))
+        .collect::<Vec<usize>>();
+
+    OverlayReport {
+        num_towers: towers.len(),
+        connected: connected_components(overlay) == 1,
+        strongly_connected,
+        num_sccs: sccs.len(),
+        scc_sizes: dist_stats(&sccs.iter().map(|scc| scc.len()).collect()),
+        in_degree: dist_stats(&in_degrees),
+        out_degree: dist_stats(&out_degrees),
+        diameter,
+        condensation_depth: condensation_depth(&sccs, overlay),
+        isolated_towers,
+        isolated_colors,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::gen_network;
+    use towers::{choose_towers, calc_towers_info, towers_overlay, is_connected};
+
+    #[test]
+    fn test_overlay_report_small() {
+        // 1 <-> 2 -> 3 -> 4 <-> 5, and 6 on its own:
+        let mut overlay: graphmap::DiGraphMap<usize,(u64, usize)> = graphmap::DiGraphMap::new();
+        overlay.add_edge(1, 2, (1, 0));
+        overlay.add_edge(2, 1, (1, 1));
+        overlay.add_edge(2, 3, (1, 0));
+        overlay.add_edge(3, 4, (1, 1));
+        overlay.add_edge(4, 5, (1, 0));
+        overlay.add_edge(5, 4, (1, 1));
+        overlay.add_edge(6, 6, (0, 2));
+        let chosen_towers = vec![vec![1, 3, 5], vec![2, 4], vec![6]];
+
+        let report = overlay_report(&chosen_towers, &overlay);
+        assert!(report.num_towers == 6);
+        assert!(!report.connected);
+        assert!(!report.strongly_connected);
+        assert!(report.num_sccs == 4);
+        assert!(report.scc_sizes.max == 2);
+        assert!(report.out_degree.max == 2);
+        assert!(report.in_degree.max == 2);
+        assert!(report.diameter == None);
+        // {1,2} -> {3} -> {4,5}:
+        assert!(report.condensation_depth == 3);
+        // 3 is a component of its own, but it is linked from 2 and links to 4:
+        assert!(report.isolated_towers == vec![6]);
+        // The largest component is {1,2}:
+        assert!(report.isolated_colors == vec![2]);
+    }
+
+    #[test]
+    fn test_overlay_report_single_tower() {
+        let mut overlay: graphmap::DiGraphMap<usize,(u64, usize)> = graphmap::DiGraphMap::new();
+        overlay.add_edge(7, 7, (0, 0));
+        let chosen_towers = vec![vec![7]];
+
+        let report = overlay_report(&chosen_towers, &overlay);
+        assert!(report.num_towers == 1);
+        assert!(report.strongly_connected);
+        assert!(report.isolated_towers.is_empty());
+        assert!(report.isolated_colors.is_empty());
+    }
+
+    #[test]
+    fn test_overlay_report() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 2, &mut rng);
+        let chosen_towers = choose_towers(&net, 4, 16, &mut rng);
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+        let overlay = towers_overlay(&chosen_towers, &towers_info);
+
+        let report = overlay_report(&chosen_towers, &overlay);
+        assert!((report.connected, report.strongly_connected) ==
+                is_connected(&chosen_towers, &towers_info));
+        assert!(report.strongly_connected);
+        assert!(report.num_sccs == 1);
+        assert!(report.scc_sizes.max == report.num_towers);
+        assert!(report.condensation_depth == 1);
+        assert!(report.diameter.unwrap() >= 1);
+        assert!(report.isolated_towers.is_empty());
+        assert!(report.isolated_colors.is_empty());
+        // Every tower links to at most one tower of every color:
+        assert!(report.out_degree.max <= chosen_towers.len());
+    }
+}
@@ -0,0 +1,446 @@
+extern crate rand;
+extern crate petgraph;
+
+use std::cmp::{min, Reverse};
+use std::collections::{HashSet};
+use std::hash::{Hash};
+use std::u64;
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+use self::petgraph::algo::{dijkstra};
+use self::petgraph::visit::{EdgeRef};
+
+use network::{Network};
+use chord::{RingKey};
+use hash_util::{sha256_hash};
+use random_util::{choose_k_nums};
+use towers::{choose_towers};
+
+
+/// A strategy for placing towers in a network.
+pub trait TowerPlacement<Node> {
+    /// Short name of the strategy, used for printing experiments results.
+    fn name(&self) -> &str;
+
+    /// Choose towers for every color (Indices of nodes). num_towers is the wanted amount of
+    /// towers of every color. Some strategies only approximate this amount, but every color
+    /// always gets at least one tower.
+    /// The towers of every color are sorted, for determinism.
+    fn place(&self, net: &Network<Node>, num_towers: usize, num_colors: usize,
+             rng: &mut dyn Rng) -> Vec<Vec<usize>>;
+}
+
+/// Choose towers of every color uniformly at random (See choose_towers).
+pub struct RandomPlacement;
+
+/// Towers of the same color are at least min_dist apart.
+/// Candidates are checked in random order. If there are not enough candidates that are far
+/// enough, the remaining towers are chosen to be as far as possible from the chosen towers.
+/// A node is never chosen twice for the same color, even if min_dist is 0.
+pub struct SpreadPlacement {
+    pub min_dist: u64,
+}
+
+/// Choose towers of every color randomly, with probability proportional to node degree.
+/// Nodes of degree 0 can not be chosen this way. If there are not enough nodes with
+/// neighbours, the remaining towers are chosen uniformly among the isolated nodes.
+pub struct DegreeBiasedPlacement;
+
+/// Distributed self election: A node is a tower of color c if hash(salt, node, c) is below a
+/// threshold. The threshold is set so that the expected amount of towers of every color is
+/// num_towers.
+///
+/// A color without towers is given the node with the smallest hash, so that every color has a
+/// tower. Unlike the threshold check, this fallback is not local: Finding the smallest hash
+/// requires global knowledge, or a network wide computation (For example flooding the
+/// minimum). The fallback is rare when num_towers is not very small.
+pub struct HashPlacement {
+    pub salt: u64,
+}
+
+/// Fully deterministic placement: Nodes are sorted by their ids, and the towers of all colors
+/// are spread evenly along the sorted list.
+pub struct IdsPlacement;
+
+
+impl<Node: Hash + Eq + Clone> TowerPlacement<Node> for RandomPlacement {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn place(&self, net: &Network<Node>, num_towers: usize, num_colors: usize,
+             mut rng: &mut dyn Rng) -> Vec<Vec<usize>> {
+        choose_towers(net, num_towers, num_colors, &mut rng)
+    }
+}
+
+impl<Node: Hash + Eq + Clone> TowerPlacement<Node> for SpreadPlacement {
+    fn name(&self) -> &str {
+        "spread"
+    }
+
+    fn place(&self, net: &Network<Node>, num_towers: usize, num_colors: usize,
+             mut rng: &mut dyn Rng) -> Vec<Vec<usize>> {
+
+        let num_nodes = net.igraph.node_count();
+        let num_towers = min(num_towers, num_nodes);
+        let mut chosen_towers: Vec<Vec<usize>> = Vec::new();
+
+        for _ in 0 .. num_colors {
+            let mut candidates = (0 .. num_nodes).collect::<Vec<usize>>();
+            (&mut rng).shuffle(&mut candidates);
+
+            // Distance from every node to the closest chosen tower of this color:
+            let mut min_dists: Vec<u64> = vec![u64::MAX; num_nodes];
+            let mut ctowers: Vec<usize> = Vec::new();
+            let mut is_tower: Vec<bool> = vec![false; num_nodes];
+            let mut candidates_iter = candidates.into_iter();
+
+            while ctowers.len() < num_towers {
+                let far_candidate = candidates_iter
+                    .find(|&x_i| !is_tower[x_i] && min_dists[x_i] >= self.min_dist);
+                let next_tower = match far_candidate {
+                    Some(x_i) => x_i,
+                    // Not enough far candidates. Take the farthest node that is not a tower yet.
+                    // There is always such a node, because num_towers <= num_nodes.
+                    // Ties are broken by node index:
+                    None => (0 .. num_nodes)
+                        .filter(|&x_i| !is_tower[x_i])
+                        .max_by_key(|&x_i| (min_dists[x_i], Reverse(x_i)))
+                        .unwrap(),
+                };
+                is_tower[next_tower] = true;
+                ctowers.push(next_tower);
+
+                let scores = dijkstra(&net.igraph, next_tower, None, |e| *e.weight());
+                for (&x_i, &dist) in &scores {
+                    min_dists[x_i] = min(min_dists[x_i], dist);
+                }
+            }
+            ctowers.sort();
+            chosen_towers.push(ctowers);
+        }
+        chosen_towers
+    }
+}
+
+impl<Node: Hash + Eq + Clone> TowerPlacement<Node> for DegreeBiasedPlacement {
+    fn name(&self) -> &str {
+        "degree"
+    }
+
+    fn place(&self, net: &Network<Node>, num_towers: usize, num_colors: usize,
+             mut rng: &mut dyn Rng) -> Vec<Vec<usize>> {
+
+        let num_nodes = net.igraph.node_count();
+        let num_towers = min(num_towers, num_nodes);
+
+        // Cumulative degrees of all nodes:
+        let mut cum_degrees: Vec<usize> = Vec::new();
+        let mut total_degree = 0;
+        let mut isolated: Vec<usize> = Vec::new();
+        for x_i in 0 .. num_nodes {
+            let degree = net.igraph.neighbors(x_i).count();
+            if degree == 0 {
+                isolated.push(x_i);
+            }
+            total_degree += degree;
+            cum_degrees.push(total_degree);
+        }
+        // Only nodes with neighbours can be chosen by degree:
+        let num_biased = min(num_towers, num_nodes - isolated.len());
+
+        let mut chosen_towers: Vec<Vec<usize>> = Vec::new();
+        for _ in 0 .. num_colors {
+            let mut ctowers: HashSet<usize> = HashSet::new();
+            if num_biased > 0 {
+                let degree_range: Range<usize> = Range::new(0, total_degree);
+                while ctowers.len() < num_biased {
+                    let r = degree_range.ind_sample(&mut rng);
+                    // The first node with cumulative degree above r:
+                    let x_i = match cum_degrees.binary_search(&r) {
+                        Ok(pos) => pos + 1,
+                        Err(pos) => pos,
+                    };
+                    // binary_search may return any of a few equal cumulative degrees.
+                    // Skip forward over isolated nodes:
+                    let x_i = (x_i .. num_nodes)
+                        .find(|&x_i| cum_degrees[x_i] > r)
+                        .unwrap();
+                    ctowers.insert(x_i);
+                }
+            }
+            // Not enough nodes with neighbours. Choose the rest among the isolated nodes:
+            if ctowers.len() < num_towers {
+                for i in choose_k_nums(num_towers - ctowers.len(), isolated.len(), &mut rng) {
+                    ctowers.insert(isolated[i]);
+                }
+            }
+            let mut ctowers = ctowers.into_iter().collect::<Vec<usize>>();
+            // Sort for determinism:
+            ctowers.sort();
+            chosen_towers.push(ctowers);
+        }
+        chosen_towers
+    }
+}
+
+/// Hash a node together with a salt value and a color.
+fn hash_node_color<Node: Hash>(salt: u64, node: &Node, tower_color: usize) -> u64 {
+    sha256_hash(salt, &(node, tower_color))
+}
+
+impl<Node: Hash + Eq + Clone> TowerPlacement<Node> for HashPlacement {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn place(&self, net: &Network<Node>, num_towers: usize, num_colors: usize,
+             _rng: &mut dyn Rng) -> Vec<Vec<usize>> {
+
+        let num_nodes = net.igraph.node_count();
+        if num_nodes == 0 {
+            return vec![Vec::new(); num_colors];
+        }
+        let threshold = (u64::MAX / (num_nodes as u64)).saturating_mul(num_towers as u64);
+
+        (0 .. num_colors)
+            .map(|tower_color| {
+                let hashes = (0 .. num_nodes)
+                    .map(|x_i| hash_node_color(self.salt, net.index_to_node(x_i).unwrap(),
+                                               tower_color))
+                    .collect::<Vec<u64>>();
+                // Every node could check this condition locally:
+                let ctowers = (0 .. num_nodes)
+                    .filter(|&x_i| hashes[x_i] < threshold)
+                    .collect::<Vec<usize>>();
+                if !ctowers.is_empty() {
+                    return ctowers;
+                }
+                vec![(0 .. num_nodes).min_by_key(|&x_i| (hashes[x_i], x_i)).unwrap()]
+            })
+            .collect()
+    }
+}
+
+impl<Node: Hash + Eq + Clone + Ord> TowerPlacement<Node> for IdsPlacement {
+    fn name(&self) -> &str {
+        "ids"
+    }
+
+    fn place(&self, net: &Network<Node>, num_towers: usize, num_colors: usize,
+             _rng: &mut dyn Rng) -> Vec<Vec<usize>> {
+
+        let num_nodes = net.igraph.node_count();
+        let mut nodes = (0 .. num_nodes).collect::<Vec<usize>>();
+        nodes.sort_by_key(|&x_i| net.index_to_node(x_i).unwrap().clone());
+
+        // Slots along the sorted list of nodes. Slot number (i * num_colors + c) is
+        // tower number i of color c.
+        let num_slots = num_towers * num_colors;
+        (0 .. num_colors)
+            .map(|tower_color| {
+                let mut ctowers = (0 .. num_towers)
+                    .map(|i| nodes[((i * num_colors + tower_color) * num_nodes) / num_slots])
+                    .collect::<Vec<usize>>();
+                ctowers.sort();
+                ctowers.dedup();
+                ctowers
+            })
+            .collect()
+    }
+}
+
+/// All tower placement strategies, for networks generated by gen_network.
+pub fn tower_placements() -> Vec<Box<dyn TowerPlacement<RingKey>>> {
+    vec![
+        Box::new(RandomPlacement),
+        Box::new(SpreadPlacement {min_dist: 4}),
+        Box::new(DegreeBiasedPlacement),
+        Box::new(HashPlacement {salt: 0x1337}),
+        Box::new(IdsPlacement),
+    ]
+}
+
+/// Choose a tower placement strategy (With default parameters) by name.
+/// Returns None if there is no strategy with the given name.
+pub fn tower_placement_by_name(name: &str) -> Option<Box<dyn TowerPlacement<RingKey>>> {
+    tower_placements().into_iter()
+        .find(|placement| placement.name() == name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::{gen_network};
+    use towers::{calc_towers_info, is_towers_info_filled};
+
+    #[test]
+    fn test_placements_basic() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 8, 17, 1, 2, &mut rng);
+        let num_towers = 3;
+        let num_colors = 10;
+
+        for placement in tower_placements() {
+            let chosen_towers = placement.place(&net, num_towers, num_colors, &mut rng);
+            assert!(chosen_towers.len() == num_colors, "{}", placement.name());
+            for ctowers in &chosen_towers {
+                assert!(ctowers.len() > 0, "{}", placement.name());
+                // Sorted and unique:
+                assert!(ctowers.windows(2).all(|w| w[0] < w[1]), "{}", placement.name());
+                assert!(ctowers.iter().all(|&x_i| x_i < net.igraph.node_count()));
+                if placement.name() != "hash" {
+                    assert!(ctowers.len() == num_towers, "{}", placement.name());
+                }
+            }
+            let towers_info = calc_towers_info(&net, &chosen_towers);
+            assert!(is_towers_info_filled(&towers_info));
+        }
+    }
+
+    #[test]
+    fn test_hash_placement_empty_network() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net: Network<RingKey> = Network::new();
+        let placement = HashPlacement {salt: 0x1337};
+        let chosen_towers = placement.place(&net, 3, 4, &mut rng);
+        assert!(chosen_towers == vec![Vec::<usize>::new(); 4]);
+    }
+
+    #[test]
+    fn test_tower_placement_by_name() {
+        for name in &["random", "spread", "degree", "hash", "ids"] {
+            assert!(tower_placement_by_name(name).unwrap().name() == *name);
+        }
+        assert!(tower_placement_by_name("no_such_placement").is_none());
+    }
+
+    #[test]
+    fn test_spread_placement() {
+        // A path network 0 - 1 - ... - 19
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 20 {
+            net.add_node(x);
+        }
+        for x in 0 .. 19 {
+            net.igraph.add_edge(x, x + 1, 1);
+        }
+
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let chosen_towers = SpreadPlacement {min_dist: 6}.place(&net, 3, 4, &mut rng);
+        for ctowers in &chosen_towers {
+            assert!(ctowers.len() == 3);
+            assert!(ctowers.windows(2).all(|w| w[1] - w[0] >= 6));
+        }
+
+        // Impossible minimal distance. Still get the wanted amount of towers:
+        let chosen_towers = SpreadPlacement {min_dist: 100}.place(&net, 3, 4, &mut rng);
+        for ctowers in &chosen_towers {
+            assert!(ctowers.len() == 3);
+            // The two ends of the path are the farthest nodes:
+            assert!(ctowers.contains(&0) || ctowers.contains(&19));
+        }
+    }
+
+    #[test]
+    fn test_degree_biased_placement() {
+        // A star: 0 is connected to 1 .. 20
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 21 {
+            net.add_node(x);
+        }
+        for x in 1 .. 21 {
+            net.igraph.add_edge(0, x, 1);
+        }
+
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let chosen_towers = DegreeBiasedPlacement.place(&net, 2, 100, &mut rng);
+        // The center has half of the total degree. It is chosen for about 75% of the colors,
+        // compared to about 10% with uniform placement:
+        let center_count = chosen_towers.iter().filter(|ctowers| ctowers.contains(&0)).count();
+        assert!(center_count > 60);
+    }
+
+    #[test]
+    fn test_degree_biased_placement_isolated_nodes() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+
+        // No edges at all:
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 6 {
+            net.add_node(x);
+        }
+        let chosen_towers = DegreeBiasedPlacement.place(&net, 2, 3, &mut rng);
+        assert!(chosen_towers.iter().all(|ctowers| ctowers.len() == 2));
+
+        // Only 0 and 1 have neighbours, but 4 towers are wanted:
+        net.igraph.add_edge(0, 1, 1);
+        let chosen_towers = DegreeBiasedPlacement.place(&net, 4, 3, &mut rng);
+        for ctowers in &chosen_towers {
+            assert!(ctowers.len() == 4);
+            assert!(ctowers.contains(&0) && ctowers.contains(&1));
+        }
+
+        // An isolated node is never chosen by degree:
+        let chosen_towers = DegreeBiasedPlacement.place(&net, 1, 50, &mut rng);
+        assert!(chosen_towers.iter().all(|ctowers| ctowers[0] < 2));
+    }
+
+    #[test]
+    fn test_spread_placement_zero_dists() {
+        // A path network with edges of length 0. All distances are 0:
+        let mut net = Network::<usize>::new();
+        for x in 0 .. 5 {
+            net.add_node(x);
+        }
+        for x in 0 .. 4 {
+            net.igraph.add_edge(x, x + 1, 0);
+        }
+
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        for &min_dist in &[0, 1] {
+            let chosen_towers = SpreadPlacement {min_dist}.place(&net, 5, 3, &mut rng);
+            assert!(chosen_towers.iter().all(|ctowers| *ctowers == vec![0, 1, 2, 3, 4]));
+        }
+    }
+
+    #[test]
+    fn test_hash_node_color() {
+        assert!(hash_node_color(1, &5_u64, 2) == sha256_hash(1, &(5_u64, 2_usize)));
+        assert!(hash_node_color(1, &5_u64, 2) != hash_node_color(1, &5_u64, 3));
+        assert!(hash_node_color(1, &5_u64, 2) != hash_node_color(2, &5_u64, 2));
+    }
+
+    #[test]
+    fn test_deterministic_placements() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 10, 21, 1, 2, &mut rng);
+
+        let placement = HashPlacement {salt: 1};
+        let chosen_towers = placement.place(&net, 8, 6, &mut rng);
+        assert!(chosen_towers == placement.place(&net, 8, 6, &mut rng));
+        // Amount of towers should be close to the expected amount:
+        let total_towers = chosen_towers.iter().map(|ctowers| ctowers.len()).sum::<usize>();
+        assert!(total_towers > 6 * 8 / 2 && total_towers < 6 * 8 * 2);
+        assert!(chosen_towers != HashPlacement {salt: 2}.place(&net, 8, 6, &mut rng));
+
+        let chosen_towers = IdsPlacement.place(&net, 8, 6, &mut rng);
+        assert!(chosen_towers == IdsPlacement.place(&net, 8, 6, &mut rng));
+        // All towers are distinct:
+        let all_towers = chosen_towers.iter()
+            .flat_map(|ctowers| ctowers.iter().cloned())
+            .collect::<HashSet<usize>>();
+        assert!(all_towers.len() == 6 * 8);
+    }
+}
@@ -0,0 +1,444 @@
+extern crate petgraph;
+
+use std::hash::Hash;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, BinaryHeap};
+
+use self::petgraph::graphmap;
+
+use network::{Network};
+use router::{Router, RouteSummary};
+use towers::{LocalTowerInfo, path_to_tower};
+use towers::k_closest::{KTowerInfo};
+
+/// A full route of a message over the towers overlay.
+pub struct TowersRoute {
+    /// All the nodes the message has passed through, including src_node and dst_node.
+    pub path: Vec<usize>,
+    /// Sum of weights of all edges along the path.
+    pub weight: u64,
+    /// Amount of tower to tower hops in the overlay.
+    pub overlay_hops: usize,
+}
+
+impl TowersRoute {
+    pub fn hops(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    fn summary(&self) -> RouteSummary {
+        RouteSummary {hops: self.hops(), weight: self.weight}
+    }
+}
+
+/// Routing over the towers overlay, as a Router. (See find_path_towers)
+pub struct TowersRouter<'a> {
+    pub towers_info: &'a Vec<Vec<Option<LocalTowerInfo>>>,
+    pub overlay: &'a graphmap::DiGraphMap<usize,(u64, usize)>,
+}
+
+/// Routing over the overlay of the k closest towers, as a Router. (See find_path_towers_k)
+pub struct TowersKRouter<'a> {
+    pub towers_info_k: &'a Vec<Vec<Vec<KTowerInfo>>>,
+    pub overlay: &'a graphmap::DiGraphMap<usize,(u64, usize)>,
+}
+
+/// Find the color of the closest local tower of a node.
+/// Ties are broken by color. Returns None if the node knows no towers.
+fn closest_tower_color(node: usize, towers_info: &Vec<Vec<Option<LocalTowerInfo>>>)
+    -> Option<usize> {
+
+    (0 .. towers_info[node].len())
+        .filter_map(|tower_color| towers_info[node][tower_color].as_ref()
+                    .map(|info| (info.distance, tower_color)))
+        .min()
+        .map(|(_, tower_color)| tower_color)
+}
+
+/// Find a shortest path (By distance labels) between two towers in the overlay graph.
+/// Returns the list of (tower_node, tower_color) overlay edges along the path.
+/// Ties are broken by node index, so the result is deterministic.
+fn overlay_path(src_tower: usize, dst_tower: usize,
+                overlay: &graphmap::DiGraphMap<usize,(u64, usize)>) -> Option<Vec<(usize, usize)>> {
+
+    // tower -> (distance, previous tower, color of the edge from the previous tower)
+    let mut visited: HashMap<usize, (u64, Option<(usize, usize)>)> = HashMap::new();
+    let mut pending: HashMap<usize, (u64, Option<(usize, usize)>)> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    pending.insert(src_tower, (0, None));
+    heap.push(Reverse((0, src_tower)));
+
+    while let Some(Reverse((dist, tower))) = heap.pop() {
+        if visited.contains_key(&tower) || pending[&tower].0 != dist {
+            continue;
+        }
+        visited.insert(tower, pending[&tower]);
+        if tower == dst_tower {
+            break;
+        }
+        for (_, next_tower, &(edge_dist, tower_color)) in overlay.edges(tower) {
+            let next_dist = dist + edge_dist;
+            if visited.contains_key(&next_tower) {
+                continue;
+            }
+            let is_better = match pending.get(&next_tower) {
+                None => true,
+                Some(&(old_dist, old_prev)) => (next_dist, Some((tower, tower_color))) < (old_dist, old_prev),
+            };
+            if is_better {
+                pending.insert(next_tower, (next_dist, Some((tower, tower_color))));
+                heap.push(Reverse((next_dist, next_tower)));
+            }
+        }
+    }
+
+    // Reconstruct the path backwards:
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    let mut cur_tower = dst_tower;
+    while let Some(prev) = visited.get(&cur_tower)?.1 {
+        edges.push(prev);
+        cur_tower = prev.0;
+    }
+    edges.reverse();
+    Some(edges)
+}
+
+/// Append a path segment to a path. The segment starts where the path ends.
+fn extend_path(path: &mut Vec<usize>, segment: &Vec<usize>) {
+    assert!(path.last() == segment.first(), "Segment does not continue the path! Aborting.");
+    path.extend_from_slice(&segment[1 ..]);
+}
+
+/// Route a message from src_node to dst_node over the towers overlay:
+/// The message climbs from src_node to its closest local tower, then moves between towers
+/// through the overlay graph, until it reaches the closest local tower of dst_node.
+/// Finally it descends from that tower to dst_node along the reversed gateways path.
+///
+/// overlay is the result of towers_overlay.
+/// Returns None if the destination tower is not reachable in the overlay.
+pub fn find_path_towers<Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize,
+         net: &Network<Node>, towers_info: &Vec<Vec<Option<LocalTowerInfo>>>,
+         overlay: &graphmap::DiGraphMap<usize,(u64, usize)>) -> Option<TowersRoute> {
+
+    let mut path = vec![src_node];
+    let mut overlay_hops = 0;
+
+    if src_node != dst_node {
+        // Climb to the local tower of src_node:
+        let up_path = path_to_tower(towers_info, src_node, 
+                                    closest_tower_color(src_node, towers_info)?)?;
+        // Path from the local tower of dst_node down to dst_node:
+        let mut down_path = path_to_tower(towers_info, dst_node, 
+                                          closest_tower_color(dst_node, towers_info)?)?;
+        down_path.reverse();
+
+        extend_path(&mut path, &up_path);
+        let src_tower = *up_path.last().unwrap();
+        let dst_tower = *down_path.first().unwrap();
+
+        for (tower_node, tower_color) in overlay_path(src_tower, dst_tower, overlay)? {
+            extend_path(&mut path, &path_to_tower(towers_info, tower_node, tower_color)?);
+            overlay_hops += 1;
+        }
+        extend_path(&mut path, &down_path);
+    }
+
+    let mut weight: u64 = 0;
+    for i in 1 .. path.len() {
+        weight += *net.igraph.edge_weight(path[i-1], path[i])?;
+    }
+
+    Some(TowersRoute {path, weight, overlay_hops})
+}
+
+/// Find the closest tower of a node among its k closest towers of all colors.
+/// Returns (tower_color, tower_node). Ties are broken by color.
+fn closest_tower_k(node: usize, towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>)
+    -> Option<(usize, usize)> {
+
+    (0 .. towers_info_k[node].len())
+        .filter_map(|tower_color| towers_info_k[node][tower_color].first()
+                    .map(|entry| (entry.info.distance, tower_color, entry.info.tower_node)))
+        .min()
+        .map(|(_, tower_color, tower_node)| (tower_color, tower_node))
+}
+
+/// Path from a node to one of its k closest towers of the given color, following the gateways.
+/// If a gateway is a failed node, the alternative gateway is used instead.
+/// The path includes both the node and the tower.
+/// Returns None if both gateways of some node along the way failed.
+fn climb_to_tower_k(node: usize, tower_color: usize, tower_node: usize,
+                    towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>,
+                    failed_nodes: &HashSet<usize>) -> Option<Vec<usize>> {
+
+    let mut path = vec![node];
+    let mut cur_node = node;
+    while cur_node != tower_node {
+        let entry = towers_info_k[cur_node][tower_color].iter()
+            .find(|entry| entry.info.tower_node == tower_node)?;
+        cur_node = if !failed_nodes.contains(&entry.info.gateway) {
+            entry.info.gateway
+        } else {
+            match entry.alt {
+                Some((alt_gateway, _)) if !failed_nodes.contains(&alt_gateway) => alt_gateway,
+                _ => return None,
+            }
+        };
+        path.push(cur_node);
+        // Protects against gateway loops, which may be caused by alternative gateways:
+        if path.len() > towers_info_k.len() {
+            return None
+        }
+    }
+    Some(path)
+}
+
+/// Route a message from src_node to dst_node over the overlay of the k closest towers.
+/// Works like find_path_towers, but every tower may use the links to all of its k closest
+/// towers of every color.
+///
+/// overlay is the result of towers_overlay_k.
+/// Returns None if the destination tower is not reachable in the overlay.
+pub fn find_path_towers_k<Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize,
+         net: &Network<Node>, towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>,
+         overlay: &graphmap::DiGraphMap<usize,(u64, usize)>) -> Option<TowersRoute> {
+
+    find_path_towers_k_avoiding(src_node, dst_node, net, towers_info_k, overlay, &HashSet::new())
+}
+
+/// Route a message like find_path_towers_k, when some nodes have failed.
+/// Nodes do not recalculate their towers info. Whenever the gateway to a tower is a failed node,
+/// the message is sent through the alternative gateway of the entry.
+///
+/// src_node and dst_node are assumed to be alive.
+/// Returns None if the message reaches a node where both gateways failed, or if the destination
+/// tower is not reachable in the overlay.
+pub fn find_path_towers_k_avoiding<Node: Hash + Eq + Clone>(src_node: usize, dst_node: usize,
+         net: &Network<Node>, towers_info_k: &Vec<Vec<Vec<KTowerInfo>>>,
+         overlay: &graphmap::DiGraphMap<usize,(u64, usize)>,
+         failed_nodes: &HashSet<usize>) -> Option<TowersRoute> {
+
+    let mut path = vec![src_node];
+    let mut overlay_hops = 0;
+
+    if src_node != dst_node {
+        let (src_color, src_tower) = closest_tower_k(src_node, towers_info_k)?;
+        let up_path = climb_to_tower_k(src_node, src_color, src_tower, 
+                                       towers_info_k, failed_nodes)?;
+        let (dst_color, dst_tower) = closest_tower_k(dst_node, towers_info_k)?;
+        let mut down_path = climb_to_tower_k(dst_node, dst_color, dst_tower, 
+                                             towers_info_k, failed_nodes)?;
+        down_path.reverse();
+
+        extend_path(&mut path, &up_path);
+
+        let edges = overlay_path(src_tower, dst_tower, overlay)?;
+        for i in 0 .. edges.len() {
+            let (tower_node, tower_color) = edges[i];
+            let next_tower = edges.get(i + 1).map_or(dst_tower, |&(next_tower, _)| next_tower);
+            extend_path(&mut path, &climb_to_tower_k(tower_node, tower_color, next_tower,
+                                                     towers_info_k, failed_nodes)?);
+            overlay_hops += 1;
+        }
+        extend_path(&mut path, &down_path);
+    }
+
+    let mut weight: u64 = 0;
+    for i in 1 .. path.len() {
+        weight += *net.igraph.edge_weight(path[i-1], path[i])?;
+    }
+
+    Some(TowersRoute {path, weight, overlay_hops})
+}
+
+impl<'a, Node: Hash + Eq + Clone> Router<Node> for TowersRouter<'a> {
+    fn name(&self) -> &str {
+        "towers"
+    }
+
+    fn route(&self, src_node: usize, dst_node: usize, net: &Network<Node>) -> Option<RouteSummary> {
+        find_path_towers(src_node, dst_node, net, self.towers_info, self.overlay)
+            .map(|route| route.summary())
+    }
+}
+
+impl<'a, Node: Hash + Eq + Clone> Router<Node> for TowersKRouter<'a> {
+    fn name(&self) -> &str {
+        "towers_k"
+    }
+
+    fn route(&self, src_node: usize, dst_node: usize, net: &Network<Node>) -> Option<RouteSummary> {
+        find_path_towers_k(src_node, dst_node, net, self.towers_info_k, self.overlay)
+            .map(|route| route.summary())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::*;
+    use self::rand::{StdRng};
+    use network_gen::gen_network;
+    use towers::{choose_towers, calc_towers_info, towers_overlay, is_connected,
+                 overlay_connectivity, TowersDist};
+    use towers::k_closest::{calc_towers_info_k, drop_failed_towers, towers_overlay_k};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_find_path_towers() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 2, &mut rng);
+
+        let chosen_towers = choose_towers(&net, 4, 16, &mut rng);
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+        assert!(is_connected(&chosen_towers, &towers_info) == (true, true));
+        let overlay = towers_overlay(&chosen_towers, &towers_info);
+
+        let num_nodes = net.igraph.node_count();
+        for src_node in (0 .. num_nodes).step_by(5) {
+            for dst_node in (0 .. num_nodes).step_by(3) {
+                // The overlay is strongly connected, hence routing always succeeds:
+                let route = find_path_towers(src_node, dst_node, &net,
+                                             &towers_info, &overlay).unwrap();
+                assert!(route.path[0] == src_node);
+                assert!(*route.path.last().unwrap() == dst_node);
+                assert!(route.weight >= net.dist(src_node, dst_node).unwrap());
+                assert!(route.weight == route.hops() as u64);
+            }
+        }
+
+        let route = find_path_towers(3, 3, &net, &towers_info, &overlay).unwrap();
+        assert!(route.path == vec![3]);
+        assert!(route.weight == 0);
+    }
+
+    #[test]
+    fn test_find_path_towers_k() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 10, &mut rng);
+        let chosen_towers = choose_towers(&net, 2, 8, &mut rng);
+        let towers_info_k = calc_towers_info_k(&net, &chosen_towers, 3, TowersDist::Weighted);
+
+        // Fail one tower of every color:
+        let failed_towers = chosen_towers.iter()
+            .map(|ctowers| ctowers[0])
+            .collect::<HashSet<usize>>();
+        let towers_info_k = drop_failed_towers(&towers_info_k, &failed_towers);
+        let overlay = towers_overlay_k(&chosen_towers, &towers_info_k, &failed_towers);
+        assert!(overlay_connectivity(&overlay) == (true, true));
+
+        let num_nodes = net.igraph.node_count();
+        for src_node in (0 .. num_nodes).step_by(5) {
+            for dst_node in (0 .. num_nodes).step_by(3) {
+                let route = find_path_towers_k(src_node, dst_node, &net,
+                                               &towers_info_k, &overlay).unwrap();
+                assert!(route.path[0] == src_node);
+                assert!(*route.path.last().unwrap() == dst_node);
+                assert!(route.weight >= net.dist(src_node, dst_node).unwrap());
+                assert!(route.overlay_hops < overlay.node_count());
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_path_towers_k_avoiding() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 10, &mut rng);
+        let chosen_towers = choose_towers(&net, 2, 8, &mut rng);
+        let towers_info_k = calc_towers_info_k(&net, &chosen_towers, 2, TowersDist::Weighted);
+        let overlay = towers_overlay_k(&chosen_towers, &towers_info_k, &HashSet::new());
+
+        let num_nodes = net.igraph.node_count();
+        // Without failures, routing is the same as find_path_towers_k:
+        for src_node in (0 .. num_nodes).step_by(7) {
+            for dst_node in (0 .. num_nodes).step_by(5) {
+                let route = find_path_towers_k(src_node, dst_node, &net,
+                                               &towers_info_k, &overlay).unwrap();
+                let route_avoiding = find_path_towers_k_avoiding(src_node, dst_node, &net,
+                                               &towers_info_k, &overlay, &HashSet::new()).unwrap();
+                assert!(route.path == route_avoiding.path);
+            }
+        }
+
+        // Fail some nodes that are not towers:
+        let all_towers = chosen_towers.iter()
+            .flat_map(|ctowers| ctowers.iter().cloned())
+            .collect::<HashSet<usize>>();
+        let failed_nodes = (0 .. num_nodes)
+            .step_by(11)
+            .filter(|node| !all_towers.contains(node))
+            .collect::<HashSet<usize>>();
+        let alive_nodes = (0 .. num_nodes)
+            .filter(|node| !failed_nodes.contains(node))
+            .collect::<Vec<usize>>();
+
+        let mut num_primary = 0;
+        let mut num_avoiding = 0;
+        for &src_node in alive_nodes.iter().step_by(7) {
+            for &dst_node in alive_nodes.iter().step_by(5) {
+                let route = find_path_towers_k(src_node, dst_node, &net,
+                                               &towers_info_k, &overlay).unwrap();
+                if route.path.iter().all(|node| !failed_nodes.contains(node)) {
+                    num_primary += 1;
+                }
+                if let Some(route) = find_path_towers_k_avoiding(src_node, dst_node, &net,
+                                               &towers_info_k, &overlay, &failed_nodes) {
+                    assert!(route.path[0] == src_node);
+                    assert!(*route.path.last().unwrap() == dst_node);
+                    assert!(route.path.iter().all(|node| !failed_nodes.contains(node)));
+                    num_avoiding += 1;
+                }
+            }
+        }
+        // Alternative gateways route around failed nodes:
+        assert!(num_avoiding > num_primary);
+    }
+
+    #[test]
+    fn test_towers_routers() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let net = gen_network(0, 7, 15, 1, 2, &mut rng);
+        let chosen_towers = choose_towers(&net, 4, 16, &mut rng);
+
+        let towers_info = calc_towers_info(&net, &chosen_towers);
+        let overlay = towers_overlay(&chosen_towers, &towers_info);
+        let router = TowersRouter {towers_info: &towers_info, overlay: &overlay};
+
+        let towers_info_k = calc_towers_info_k(&net, &chosen_towers, 2, TowersDist::Hops);
+        let overlay_k = towers_overlay_k(&chosen_towers, &towers_info_k, &HashSet::new());
+        let router_k = TowersKRouter {towers_info_k: &towers_info_k, overlay: &overlay_k};
+
+        let routers: Vec<&dyn Router<u64>> = vec![&router, &router_k];
+        for src_node in 0 .. 10 {
+            for dst_node in 20 .. 30 {
+                let route = find_path_towers(src_node, dst_node, &net,
+                                             &towers_info, &overlay).unwrap();
+                assert!(routers[0].route(src_node, dst_node, &net) == Some(route.summary()));
+                let route_k = find_path_towers_k(src_node, dst_node, &net,
+                                                 &towers_info_k, &overlay_k).unwrap();
+                assert!(routers[1].route(src_node, dst_node, &net) == Some(route_k.summary()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_overlay_path() {
+        // 1 -> 2 -> 3, and a long direct edge 1 -> 3:
+        let mut overlay: graphmap::DiGraphMap<usize,(u64, usize)> = graphmap::DiGraphMap::new();
+        overlay.add_edge(1, 2, (1, 0));
+        overlay.add_edge(2, 3, (1, 1));
+        overlay.add_edge(1, 3, (5, 2));
+        overlay.add_edge(3, 4, (1, 0));
+
+        assert!(overlay_path(1, 3, &overlay) == Some(vec![(1, 0), (2, 1)]));
+        assert!(overlay_path(1, 1, &overlay) == Some(vec![]));
+        assert!(overlay_path(3, 1, &overlay) == None);
+        assert!(overlay_path(1, 4, &overlay) == Some(vec![(1, 0), (2, 1), (3, 0)]));
+    }
+}
@@ -0,0 +1,264 @@
+extern crate rand;
+extern crate petgraph;
+
+use std::hash::Hash;
+use std::mem::size_of;
+
+use self::rand::{Rng};
+use self::rand::distributions::{IndependentSample, Range};
+use self::petgraph::algo::{dijkstra};
+use self::petgraph::visit::{EdgeRef};
+
+use network::{Network};
+use random_util::{choose_k_nums};
+use state_size::{NodeStateSize};
+
+/// Minimum value for a height component. Keeps the coordinates from collapsing
+/// into the height dimension.
+const MIN_HEIGHT: f64 = 0.01;
+
+/// A Vivaldi coordinate: A point in a low dimensional euclidean space, possibly with a height.
+#[derive(Clone, Debug)]
+pub struct VivaldiCoord {
+    pub pos: Vec<f64>,
+    /// Height above the euclidean space. Models the access link of a node.
+    /// Always 0 if heights are not used.
+    pub height: f64,
+    /// Local error estimate of the coordinate, in [0, 1].
+    pub error: f64,
+}
+
+pub struct VivaldiParams {
+    /// Dimension of the euclidean space.
+    pub dims: usize,
+    /// Should the coordinates have a height component.
+    pub use_height: bool,
+    /// Amount of random remote peers every node measures its distance to,
+    /// in addition to its neighbours.
+    pub num_peers: usize,
+    /// Amount of rounds. In every round every node performs one measurement.
+    pub num_rounds: usize,
+    /// Coordinate adaptation constant.
+    pub cc: f64,
+    /// Error adaptation constant.
+    pub ce: f64,
+}
+
+impl VivaldiParams {
+    pub fn new(dims: usize, use_height: bool) -> VivaldiParams {
+        VivaldiParams {
+            dims,
+            use_height,
+            num_peers: 8,
+            num_rounds: 200,
+            cc: 0.25,
+            ce: 0.25,
+        }
+    }
+}
+
+/// Euclidean norm of a vector.
+fn norm(vec: &Vec<f64>) -> f64 {
+    vec.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+/// Estimated distance between two vivaldi coordinates.
+pub fn vivaldi_dist(a: &VivaldiCoord, b: &VivaldiCoord) -> f64 {
+    let diff = a.pos.iter().zip(b.pos.iter())
+        .map(|(x, y)| x - y)
+        .collect::<Vec<f64>>();
+    norm(&diff) + a.height + b.height
+}
+
+/// Approximate distance between two nodes in the network using vivaldi coordinates.
+pub fn approx_vivaldi_dist(u: usize, v: usize, vcoords: &Vec<VivaldiCoord>) -> f64 {
+    vivaldi_dist(&vcoords[u], &vcoords[v])
+}
+
+/// Get a random unit vector of the given dimension.
+fn random_unit_vec<R: Rng>(dims: usize, rng: &mut R) -> Vec<f64> {
+    let coord_range: Range<f64> = Range::new(-1.0, 1.0);
+    loop {
+        let vec = (0 .. dims)
+            .map(|_| coord_range.ind_sample(rng))
+            .collect::<Vec<f64>>();
+        let vec_norm = norm(&vec);
+        if vec_norm > 0.0 {
+            return vec.into_iter().map(|x| x / vec_norm).collect();
+        }
+    }
+}
+
+/// Calculate the peers of every node: All of its neighbours, together with num_peers
+/// random remote nodes. Returns a list of (peer_index, measured distance) for every node.
+fn gen_peers<R: Rng, Node: Hash + Eq + Clone>(net: &Network<Node>, num_peers: usize,
+                                           rng: &mut R) -> Vec<Vec<(usize, u64)>> {
+    let num_nodes = net.igraph.node_count();
+    let mut peers: Vec<Vec<(usize, u64)>> = Vec::new();
+
+    for x_i in 0 .. num_nodes {
+        let mut node_peers = net.igraph.edges(x_i)
+            .map(|(_, nei, &weight)| (nei, weight))
+            .collect::<Vec<(usize, u64)>>();
+
+        if num_peers > 0 {
+            let scores = dijkstra(&net.igraph, x_i, None, |e| *e.weight());
+            let mut remote = choose_k_nums(num_peers.min(num_nodes), num_nodes, rng)
+                .into_iter()
+                .filter(|&y_i| y_i != x_i)
+                .collect::<Vec<usize>>();
+            // Sort for determinism:
+            remote.sort();
+            for y_i in remote {
+                if let Some(&dist) = scores.get(&y_i) {
+                    node_peers.push((y_i, dist));
+                }
+            }
+        }
+        peers.push(node_peers);
+    }
+    peers
+}
+
+/// Update the coordinate of node x_i according to a measurement of distance rtt to node y_i.
+fn update_coord<R: Rng>(x_i: usize, y_i: usize, rtt: f64, vcoords: &mut Vec<VivaldiCoord>,
+                        params: &VivaldiParams, rng: &mut R) {
+
+    let est = approx_vivaldi_dist(x_i, y_i, vcoords);
+    let (x_error, y_error) = (vcoords[x_i].error, vcoords[y_i].error);
+
+    // Sample weight balances local and remote error:
+    let weight = if x_error + y_error > 0.0 {
+        x_error / (x_error + y_error)
+    } else {
+        0.5
+    };
+    let sample_error = (est - rtt).abs() / rtt;
+    let new_error = sample_error * params.ce * weight + x_error * (1.0 - params.ce * weight);
+
+    let delta = params.cc * weight;
+    let force = delta * (rtt - est);
+
+    // Direction from y to x. If both positions are at the same place, pick a random direction,
+    // otherwise nodes that start at the same position never separate:
+    let diff = vcoords[x_i].pos.iter().zip(vcoords[y_i].pos.iter())
+        .map(|(x, y)| x - y)
+        .collect::<Vec<f64>>();
+    let diff_norm = norm(&diff);
+    let heights = vcoords[x_i].height + vcoords[y_i].height;
+    let (direction, height_direction) = if diff_norm > 0.0 {
+        (diff.into_iter().map(|d| d / (diff_norm + heights)).collect::<Vec<f64>>(),
+         heights / (diff_norm + heights))
+    } else {
+        (random_unit_vec(params.dims, rng), 0.0)
+    };
+
+    let x_coord = &mut vcoords[x_i];
+    for (p, d) in x_coord.pos.iter_mut().zip(direction.iter()) {
+        *p += force * d;
+    }
+    if params.use_height {
+        x_coord.height = (x_coord.height + force * height_direction).max(MIN_HEIGHT);
+    }
+    x_coord.error = new_error.min(1.0);
+}
+
+/// Calculate vivaldi coordinates for all nodes in the network.
+/// In every round every node measures the distance to one random peer (A neighbour or a random
+/// remote node), and moves its own coordinate according to the measurement.
+pub fn build_vivaldi_coords<R: Rng, Node: Hash + Eq + Clone>(net: &Network<Node>,
+                    params: &VivaldiParams, rng: &mut R) -> Vec<VivaldiCoord> {
+
+    let num_nodes = net.igraph.node_count();
+    let peers = gen_peers(net, params.num_peers, rng);
+
+    // All nodes start at the origin, with maximal error:
+    let mut vcoords = vec![VivaldiCoord {
+        pos: vec![0.0; params.dims],
+        height: if params.use_height { MIN_HEIGHT } else { 0.0 },
+        error: 1.0,
+    }; num_nodes];
+
+    for _ in 0 .. params.num_rounds {
+        for x_i in 0 .. num_nodes {
+            if peers[x_i].is_empty() {
+                continue;
+            }
+            let peer_range: Range<usize> = Range::new(0, peers[x_i].len());
+            let (y_i, dist) = peers[x_i][peer_range.ind_sample(rng)];
+            update_coord(x_i, y_i, dist as f64, &mut vcoords, params, rng);
+        }
+    }
+    vcoords
+}
+
+/// Size of the vivaldi coordinate every node keeps.
+pub fn vivaldi_state_sizes(vcoords: &Vec<VivaldiCoord>) -> Vec<NodeStateSize> {
+    vcoords.iter()
+        .map(|vcoord| NodeStateSize {
+            // Position, height and error:
+            entries: vcoord.pos.len() + 2,
+            bytes: (vcoord.pos.len() + 2) * size_of::<f64>(),
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::rand::{StdRng};
+    use network::{grid2_net};
+    use network_gen::{gen_network};
+    use statistic::{spearman};
+    use landmarks::greedy_dfs::{find_path_dfs};
+
+    #[test]
+    fn test_vivaldi_dist() {
+        let a = VivaldiCoord {pos: vec![0.0, 0.0], height: 1.0, error: 1.0};
+        let b = VivaldiCoord {pos: vec![3.0, 4.0], height: 2.0, error: 1.0};
+        assert!((vivaldi_dist(&a, &b) - 8.0).abs() < 0.0001);
+        assert!((vivaldi_dist(&a, &a) - 2.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_vivaldi_grid_embedding() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        // A 2d grid could be embedded almost perfectly in 2 dimensions:
+        let net = grid2_net(8);
+        let params = VivaldiParams::new(2, false);
+        let vcoords = build_vivaldi_coords(&net, &params, &mut rng);
+        assert!(vcoords.iter().all(|vcoord| vcoord.pos.len() == 2));
+
+        let mut estimates: Vec<f64> = Vec::new();
+        let mut dists: Vec<f64> = Vec::new();
+        for u in 0 .. net.igraph.node_count() {
+            for v in (u + 1) .. net.igraph.node_count() {
+                estimates.push(approx_vivaldi_dist(u, v, &vcoords));
+                dists.push(net.dist(u, v).unwrap() as f64);
+            }
+        }
+        assert!(spearman(&estimates, &dists).unwrap() > 0.8);
+    }
+
+    #[test]
+    fn test_vivaldi_height_routing() {
+        let seed: &[_] = &[1,2,3,4,5];
+        let mut rng: StdRng = rand::SeedableRng::from_seed(seed);
+        let g = 7;
+        let net = gen_network(0, g, 2 * g + 1, 1, 2, &mut rng);
+        let params = VivaldiParams::new(3, true);
+        let vcoords = build_vivaldi_coords(&net, &params, &mut rng);
+        assert!(vcoords.iter().all(|vcoord| vcoord.height >= MIN_HEIGHT));
+
+        // Vivaldi coordinates plug into the greedy routers:
+        let node_dist = |x, y| approx_vivaldi_dist(x, y, &vcoords);
+        for dst_node in 0 .. 10 {
+            assert!(find_path_dfs(0, dst_node, &net, &node_dist).is_some());
+        }
+
+        let sizes = vivaldi_state_sizes(&vcoords);
+        assert!(sizes[0].entries == 5);
+    }
+}